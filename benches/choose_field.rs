@@ -0,0 +1,53 @@
+//! Parses queries against a schema of 500 fields and renders their SQL, run by `cargo bench`.
+
+use permeable::AllowAllPermission;
+use std::time::Instant;
+use suchbar::DbType::{INTEGER, TEXT};
+use suchbar::{DbField, Suchbar};
+
+const FIELDS: usize = 500;
+const ROUNDS: u32 = 2_000;
+
+fn leak(s: String) -> &'static str {
+    Box::leak(s.into_boxed_str())
+}
+
+fn schema() -> &'static [DbField] {
+    let fields = (0..FIELDS)
+        .map(|i| {
+            let aliases: &'static [&'static str] = Box::leak(Box::new([
+                leak(format!("field{i}")),
+                leak(format!("f{i}")),
+                leak(format!("alias_{i}")),
+            ]));
+            let db_type = if i % 2 == 0 { TEXT } else { INTEGER(0, 1000) };
+            DbField::new(leak(format!("column_{i}")), db_type, "STD", aliases)
+        })
+        .collect::<Vec<_>>();
+    Box::leak(fields.into_boxed_slice())
+}
+
+fn run(suchbar: &Suchbar, name: &str, query: &str) {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let exec = suchbar
+            .exec(&AllowAllPermission(), query)
+            .expect("the query parses");
+        assert!(exec.where_clause().is_ok());
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{ROUNDS} queries, {name} on {FIELDS} fields: {elapsed:?}, {:?} per query",
+        elapsed / ROUNDS
+    );
+}
+
+fn main() {
+    let suchbar = Suchbar::new(schema());
+    let query = (0..20)
+        .map(|i| format!("f{}=5", FIELDS - 1 - i * 7))
+        .collect::<Vec<_>>()
+        .join(" ");
+    run(&suchbar, "20 terms", &query);
+    run(&suchbar, "a field-free term", "5");
+}
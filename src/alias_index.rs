@@ -0,0 +1,63 @@
+use crate::db_field::DbField;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+
+/// Positions of the fields by their aliases.
+pub(crate) type AliasIndex = HashMap<&'static str, Vec<usize>>;
+
+/// The indexes by address and length of the fields.
+type Indexes = RwLock<HashMap<(usize, usize), Arc<AliasIndex>>>;
+
+/// The `AliasIndex` of `db_fields`, built on first use. A `Suchbar` can't hold the index itself
+/// and still be a constant, so the indexes are kept here by the address of the fields.
+pub(crate) fn alias_index(db_fields: &'static [DbField]) -> Arc<AliasIndex> {
+    static INDEXES: OnceLock<Indexes> = OnceLock::new();
+    let indexes = INDEXES.get_or_init(RwLock::default);
+    let key = (db_fields.as_ptr() as usize, db_fields.len());
+    if let Some(index) = indexes
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&key)
+    {
+        return Arc::clone(index);
+    }
+    let mut index = AliasIndex::new();
+    for (pos, field) in db_fields.iter().enumerate() {
+        for alias in field.alias {
+            let positions = index.entry(*alias).or_default();
+            if positions.last() != Some(&pos) {
+                positions.push(pos);
+            }
+        }
+    }
+    let index = Arc::new(index);
+    indexes
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(key, Arc::clone(&index));
+    index
+}
+
+#[cfg(test)]
+mod should {
+    use super::alias_index;
+    use crate::db_field::DbField;
+    use crate::DbType::{INTEGER, TEXT};
+    use std::sync::Arc;
+
+    static FIELDS: [DbField; 3] = [
+        DbField::new("name", TEXT, "STD", &["name", "n", "name"]),
+        DbField::new("age", INTEGER(0, 150), "STD", &["age", "a"]),
+        DbField::new("alias", TEXT, "STD", &["a"]),
+    ];
+
+    #[test]
+    fn index_aliases_once() {
+        let index = alias_index(&FIELDS);
+        assert_eq!(Some(&vec![0]), index.get("name"));
+        assert_eq!(Some(&vec![1, 2]), index.get("a"));
+        assert_eq!(None, index.get("nope"));
+        assert!(Arc::ptr_eq(&index, &alias_index(&FIELDS)));
+        assert_eq!(Some(&vec![0, 1]), alias_index(&FIELDS[1..]).get("a"));
+    }
+}
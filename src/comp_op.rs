@@ -1,9 +1,9 @@
-use self::CompOp::{Equal, Gt, Gte, Lt, Lte, NotEqual};
+use self::CompOp::{Equal, Gt, Gte, Lt, Lte, Match, NotEqual, NotMatch, NotSimilar, Similar};
 use crate::error::SuchError;
 use crate::error::SuchError::ParseError;
-use std::fmt::{Display, Formatter};
-use std::ops::Not;
-use std::str::FromStr;
+use core::fmt::{Display, Formatter};
+use core::ops::Not;
+use core::str::FromStr;
 
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
@@ -15,22 +15,36 @@ pub enum CompOp {
     Lt,
     Gte,
     Lte,
+    /// Regular expression match.
+    Match,
+    NotMatch,
+    /// Sounds alike, compared by `soundex`.
+    Similar,
+    NotSimilar,
+}
+
+impl CompOp {
+    /// The comparator as written in the search-language, like `>=`.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Equal => "=",
+            Gt => ">",
+            Gte => ">=",
+            Lt => "<",
+            Lte => "<=",
+            NotEqual => "!=",
+            Match => "~",
+            NotMatch => "!~",
+            Similar => "%%",
+            NotSimilar => "!%%",
+        }
+    }
 }
 
 impl Display for CompOp {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Equal => "=",
-                Gt => ">",
-                Gte => ">=",
-                Lt => "<",
-                Lte => "<=",
-                NotEqual => "!=",
-            }
-        )
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
@@ -45,6 +59,10 @@ impl FromStr for CompOp {
             "<=" | "=<" => Ok(Lte),
             "<" => Ok(Lt),
             "!=" | "=!" => Ok(NotEqual),
+            "~" => Ok(Match),
+            "!~" => Ok(NotMatch),
+            "%%" => Ok(Similar),
+            "!%%" => Ok(NotSimilar),
             _ => Err(ParseError(format!("'{s}' is no comparator!"))),
         }
     }
@@ -61,6 +79,10 @@ impl Not for CompOp {
             Gte => Lt,
             Lte => Gt,
             Lt => Gte,
+            Match => NotMatch,
+            NotMatch => Match,
+            Similar => NotSimilar,
+            NotSimilar => Similar,
         }
     }
 }
@@ -1,10 +1,17 @@
-use self::DbType::{BOOL, DATE, INTEGER, NUMERIC, TEXT, TIMESTAMP, VARCHAR};
+use self::DbType::{
+    ARRAY, BOOL, CUSTOM, DATE, ENUM, INTEGER, JSONB, LTREE, NUMERIC, TEXT, TIMESTAMP, TIMESTAMPTZ,
+    TSVECTOR, UUID, VARCHAR,
+};
 use super::comp_op::CompOp;
 use crate::error::SuchError;
 use crate::error::SuchError::ParseError;
+use crate::style::{escape_markdown, quote_value, span, Style};
+use crate::suchbar::SuchOptions;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use timewarp::{date_matcher, Direction, Doy};
 
-fn try_bool(str: &str) -> Result<bool, SuchError> {
+pub(crate) fn try_bool(str: &str) -> Result<bool, SuchError> {
     let str = str.trim().to_ascii_lowercase();
     match str.as_str() {
         "1" | "true" | "wahr" => Ok(true),
@@ -26,12 +33,245 @@ fn timestamp_checker(str: String) -> Result<String, SuchError> {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Checks `val` to be a time of day like `9`, `09:30` or `17:00:15`, returns it as `HH:MM:SS`.
+fn time_checker(val: &str) -> Result<String, SuchError> {
+    let parts = val
+        .split(':')
+        .map(|p| p.parse::<u8>().ok().filter(|_| (1..=2).contains(&p.len())))
+        .collect::<Option<Vec<_>>>();
+    match parts.as_deref() {
+        Some([h, rest @ ..]) if *h < 24 && rest.len() <= 2 && rest.iter().all(|v| *v < 60) => {
+            let m = rest.first().unwrap_or(&0);
+            let s = rest.get(1).unwrap_or(&0);
+            Ok(format!("{h:02}:{m:02}:{s:02}"))
+        }
+        _ => Err(ParseError(format!("No time of day '{val}'"))),
+    }
+}
+
+/// Checks `str` to be a ltree-path like `electronics.phones`. With `lquery` a label may be `*`.
+fn ltree_checker(str: &str, lquery: bool) -> Result<String, SuchError> {
+    let valid_label = |label: &str| {
+        (lquery && label == "*")
+            || (!label.is_empty()
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+    };
+    if str.split('.').all(valid_label) {
+        Ok(str.to_string())
+    } else {
+        Err(ParseError(format!("No path: '{str}'")))
+    }
+}
+
+/// Checks `str` to be a UUID like `67e55044-10b1-426f-9247-bb680e5fe0c8`. A value with LIKE-wildcards
+/// may be any part of it. Errors show the `input` as typed by the user.
+fn uuid_checker(str: String, input: &str) -> Result<String, SuchError> {
+    let hex_groups = |s: &str| {
+        s.split('-')
+            .map(|group| {
+                group
+                    .chars()
+                    .all(|c| c.is_ascii_hexdigit())
+                    .then_some(group.len())
+            })
+            .collect::<Option<Vec<_>>>()
+    };
+    let valid = if str.contains(['%', '_']) {
+        hex_groups(&str.replace(['%', '_', '\\'], "")).is_some()
+    } else {
+        hex_groups(&str).is_some_and(|lens| lens == [8, 4, 4, 4, 12])
+    };
+    if valid {
+        Ok(str)
+    } else {
+        Err(ParseError(format!("No UUID: '{input}'")))
+    }
+}
+
+/// Accessor of the `path` (like `a.b`) inside the json-column `column`: `column->'a'->>'b'`.
+fn json_accessor(column: &str, path: &str, q: char) -> String {
+    let mut keys = path.split('.').collect::<Vec<_>>();
+    let last = keys.pop().unwrap_or_default();
+    let mut buf = String::from(column);
+    for key in keys {
+        buf.push_str(&format!("->{q}{key}{q}"));
+    }
+    buf.push_str(&format!("->>{q}{last}{q}"));
+    buf
+}
+
+/// A path inside a `JSONB` field. It's stored inline, so a `DbField` has no destructor and can
+/// still be used in constants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct JsonPath {
+    buf: [u8; JsonPath::CAPACITY],
+    len: usize,
+}
+
+impl JsonPath {
+    const CAPACITY: usize = 64;
+
+    fn new(path: &str) -> Result<Self, SuchError> {
+        if path.len() > Self::CAPACITY {
+            return Err(ParseError(format!("Path too long: '{path}'")));
+        }
+        let mut buf = [0; Self::CAPACITY];
+        buf[..path.len()].copy_from_slice(path.as_bytes());
+        Ok(Self {
+            buf,
+            len: path.len(),
+        })
+    }
+
+    fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.buf[..self.len]).unwrap_or_default()
+    }
+}
+
+/// Finds `val` case-insensitive in the `values` of an `ENUM`. With `by_index` a number is the
+/// position of the value, starting at 1.
+fn enum_checker(values: &[&str], val: &str, by_index: bool) -> Result<String, SuchError> {
+    if let Some(index) = val.parse::<usize>().ok().filter(|_| by_index) {
+        return index
+            .checked_sub(1)
+            .and_then(|i| values.get(i))
+            .map(|v| (*v).to_string())
+            .ok_or_else(|| {
+                ParseError(format!(
+                    "No value at position {index}, there are {}",
+                    values.len()
+                ))
+            });
+    }
+    values
+        .iter()
+        .find(|v| v.eq_ignore_ascii_case(val))
+        .map(|v| (*v).to_string())
+        .ok_or_else(|| ParseError(format!("'{val}' is none of: {}", values.join(", "))))
+}
+
+/// Escapes `val` for a string quoted by `quote` by doubling the quote. Quoted other than by the
+/// standard single quote, the backslash is doubled too: MySQL takes it as an escape.
+fn escape_quote(val: &str, quote: char) -> String {
+    let mut escaped = String::with_capacity(val.len());
+    for c in val.chars() {
+        if c == quote || (c == '\\' && quote != '\'') {
+            escaped.push(c);
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// `007` is emitted as `7`, the database would read it so anyway. LIKE-patterns are kept as typed,
+/// there the zeros are part of the text searched.
+fn strip_leading_zeros(number: String) -> String {
+    if number.contains(['%', '_']) {
+        return number;
+    }
+    let (sign, digits) = match number.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", number.as_str()),
+    };
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() || digits.starts_with('.') {
+        format!("{sign}0{digits}")
+    } else {
+        format!("{sign}{digits}")
+    }
+}
+
+/// The decimal `number` has at most `precision` digits, at most `scale` of them after the point.
+/// Leading zeros don't count.
+fn fits_numeric(number: &str, precision: u32, scale: u32) -> bool {
+    let digits = number.strip_prefix('-').unwrap_or(number);
+    let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+    let plain = format!("{int}{frac}").bytes().all(|b| b.is_ascii_digit());
+    let int = int.trim_start_matches('0').len();
+    plain && frac.len() <= scale as usize && int + scale as usize <= precision as usize
+}
+
+/// Rounds the decimal `number` to `scale` digits after the point, half away from zero:
+/// `10.995` becomes `11.00` with a scale of 2. Anything but plain digits is returned as is.
+fn round_to_scale(number: &str, scale: u32) -> String {
+    let (sign, digits) = match number.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", number),
+    };
+    let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+    let scale = scale as usize;
+    let plain = !int.is_empty() && format!("{int}{frac}").bytes().all(|b| b.is_ascii_digit());
+    if !plain || frac.len() <= scale {
+        return number.to_string();
+    }
+    let mut kept = format!("{int}{}", &frac[..scale]).into_bytes();
+    if frac.as_bytes()[scale] >= b'5' {
+        match kept.iter().rposition(|b| *b != b'9') {
+            Some(i) => {
+                kept[i] += 1;
+                kept[i + 1..].fill(b'0');
+            }
+            None => {
+                kept.fill(b'0');
+                kept.insert(0, b'1');
+            }
+        }
+    }
+    let kept = String::from_utf8(kept).expect("only ascii digits");
+    let (int, frac) = kept.split_at(kept.len() - scale);
+    if frac.is_empty() {
+        format!("{sign}{int}")
+    } else {
+        format!("{sign}{int}.{frac}")
+    }
+}
+
+/// Full-text match of `db_name` against the words of `val` in the text-search `config`.
+/// Wildcards are meaningless there and get dropped.
+fn tsquery(db_name: &str, config: &str, val: &str, q: char) -> Result<String, SuchError> {
+    let words = val.replace(['*', '?'], " ");
+    let words = escape_quote(&words.split_whitespace().collect::<Vec<_>>().join(" "), q);
+    if words.is_empty() {
+        Err(ParseError(String::from("No words to search")))
+    } else {
+        Ok(format!(
+            "to_tsvector({q}{config}{q}, {db_name}) @@ plainto_tsquery({q}{config}{q}, {q}{words}{q})"
+        ))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+// transformers compare by address, the same function may rarely compare unequal to itself
+#[allow(unpredictable_function_pointer_comparisons)]
 pub struct DbField {
     pub db_name: &'static str,
     pub db_type: DbType,
+    /// The type values are checked and compared as, if it differs from `db_type`, see
+    /// `DbField::searched_as`.
+    pub search_type: Option<DbType>,
     pub permission: &'static str,
     pub alias: &'static [&'static str],
+    /// SQL-expression searched instead of the column `db_name`, see `DbField::computed`.
+    pub expr: Option<&'static str>,
+    /// Longest value accepted, overrides `DbType::max_len`, see `DbField::max_len`.
+    pub max_len: Option<usize>,
+    /// Field-free terms are searched in this field too, see `DbField::hidden`.
+    pub searchable_by_default: bool,
+    /// Field-free terms search fields of higher priority first, see `DbField::priority`.
+    pub priority: u8,
+    /// Maps the values of comparisons, see `DbField::with_transformer`.
+    pub transformer: Option<Transformer>,
+    /// The table qualifying the column, see `DbField::in_table`.
+    pub table: Option<&'static str>,
+    /// An aggregate searched in the HAVING-clause, see `DbField::aggregate`.
+    pub aggregate: bool,
+    /// The path inside a `JSONB` field, given in the query like `meta.color`.
+    pub(crate) json_path: Option<JsonPath>,
+    /// Only the time of day of a `TIMESTAMP` field is searched, given in the query like
+    /// `changed.time`.
+    pub(crate) time_of_day: bool,
 }
 
 impl DbField {
@@ -46,8 +286,146 @@ impl DbField {
         Self {
             db_name,
             db_type,
+            search_type: None,
             permission,
             alias,
+            expr: None,
+            max_len: None,
+            searchable_by_default: true,
+            priority: 0,
+            transformer: None,
+            table: None,
+            aggregate: false,
+            json_path: None,
+            time_of_day: false,
+        }
+    }
+
+    /// This field is searched only by its name: `ptext=foo` works, a field-free `foo` skips it.
+    /// For slow columns a field-free search shouldn't hit.
+    #[must_use]
+    pub const fn hidden(mut self) -> Self {
+        self.searchable_by_default = false;
+        self
+    }
+
+    /// Searches this field as `search_type` instead of its `db_type`: a view may expose an
+    /// `INTEGER` column as `TEXT`. `DbField::db_type` still reports the declared type.
+    #[must_use]
+    pub const fn searched_as(mut self, search_type: DbType) -> Self {
+        self.search_type = Some(search_type);
+        self
+    }
+
+    /// The type values are checked and compared as.
+    #[must_use]
+    pub const fn search_type(&self) -> &DbType {
+        match &self.search_type {
+            Some(search_type) => search_type,
+            None => &self.db_type,
+        }
+    }
+
+    /// Field-free terms search fields of higher `priority` first, fields of the same priority
+    /// in the order given. The default is `0`.
+    #[must_use]
+    pub const fn priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Compares this field with the value returned by `transformer` instead of checking the value
+    /// by the `DbType`: `aktiv=ja` may search `aktiv='Y'`. The returned value is quoted and
+    /// escaped and its length checked, LIKE-searches keep the value as typed.
+    ///
+    /// `transformer` is a function pointer, not a closure, so the field can still be a constant.
+    #[must_use]
+    pub const fn with_transformer(mut self, transformer: Transformer) -> Self {
+        self.transformer = Some(transformer);
+        self
+    }
+
+    /// The column lives in the joined `table`: it's always qualified by it, like
+    /// `addresses.city`, instead of by `SuchOptions::table_prefix` or a table alias.
+    #[must_use]
+    pub const fn in_table(mut self, table: &'static str) -> Self {
+        self.table = Some(table);
+        self
+    }
+
+    /// The field is an aggregate like `COUNT(o.id)`, see `DbField::computed`: its terms go to
+    /// `WhereClause::having` instead of the WHERE-clause. Field-free terms skip it, and it can
+    /// only be combined by AND with other fields.
+    #[must_use]
+    pub const fn aggregate(mut self) -> Self {
+        self.aggregate = true;
+        self.searchable_by_default = false;
+        self
+    }
+
+    /// Accepts values up to `max_len` characters instead of the default of the `DbType`.
+    #[must_use]
+    pub const fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Refuses values longer than allowed for this field, before they reach the database.
+    fn check_len(&self, val: &str) -> Result<(), SuchError> {
+        let max_len = self.max_len.unwrap_or_else(|| self.search_type().max_len());
+        if val.chars().count() > max_len {
+            Err(ParseError(format!("Value: '{val}' to long")))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// A field computed by the SQL-expression `expr`, like `first || ' ' || last`. Searching and
+    /// sorting use the expression, `db_name` only names the field.
+    ///
+    /// A table alias given to `WhereClause::to_sql_aliased` isn't applied to the expression.
+    #[must_use]
+    pub const fn computed(
+        db_name: &'static str,
+        expr: &'static str,
+        db_type: DbType,
+        permission: &'static str,
+        alias: &'static [&'static str],
+    ) -> Self {
+        Self {
+            expr: Some(expr),
+            ..Self::new(db_name, db_type, permission, alias)
+        }
+    }
+
+    /// This field with the sub-field `path`: the time of day of a `TIMESTAMP` field by `time`,
+    /// otherwise the path inside a `JSONB` field.
+    ///
+    /// # Errors
+    /// Fails if this field has no such sub-field.
+    pub(crate) fn with_path(&self, path: &str) -> Result<Self, SuchError> {
+        if matches!(self.search_type(), TIMESTAMP | TIMESTAMPTZ(_))
+            && path.eq_ignore_ascii_case("time")
+        {
+            let mut field = self.clone();
+            field.time_of_day = true;
+            Ok(field)
+        } else {
+            self.with_json_path(path)
+        }
+    }
+
+    /// This field with the `path` inside a `JSONB` field.
+    ///
+    /// # Errors
+    /// Fails if this isn't a `JSONB` field.
+    pub(crate) fn with_json_path(&self, path: &str) -> Result<Self, SuchError> {
+        if matches!(self.search_type(), JSONB) {
+            let mut field = self.clone();
+            field.json_path = Some(JsonPath::new(path)?);
+            Ok(field)
+        } else {
+            Err(ParseError(format!("Field '{}' has no path", self.db_name)))
         }
     }
 
@@ -60,41 +438,273 @@ impl DbField {
         eq: CompOp,
         val: &str,
         d: Direction,
+        options: &SuchOptions,
+        table: Option<&str>,
     ) -> Result<String, SuchError> {
-        let Self {
-            db_name, db_type, ..
-        } = self;
+        let q = options.value_quote.char();
+        if let Some(transform) = self.transformer {
+            let val = transform(val)?;
+            self.check_len(&val)?;
+            let val = escape_quote(&val, q);
+            return Ok(format!("{}{eq}{q}{val}{q}", self.quoted_column(table, q)));
+        }
+        self.check_len(val)?;
+        let db_type = self.search_type();
+        let db_name = self.quoted_column(table, q);
+        let number;
+        let val = if self.is_numeric() {
+            number = options.number(val);
+            &number
+        } else {
+            val
+        };
 
         match db_type {
+            JSONB if self.json_path.is_none() => Err(self.missing_json_path()),
+            JSONB if matches!(eq, CompOp::Gt | CompOp::Gte | CompOp::Lt | CompOp::Lte) => {
+                let number = options.number(val);
+                number
+                    .parse::<f64>()
+                    .map_err(|_| ParseError(format!("No Numeric value '{val}'")))?;
+                Ok(format!("({db_name})::numeric{eq}{number}"))
+            }
+            JSONB => Ok(format!("{db_name}{eq}{q}{}{q}", TEXT.sql_safe(val, q)?)),
+            ENUM(values) => Ok(format!(
+                "{db_name}{eq}{q}{}{q}",
+                escape_quote(&enum_checker(values, val, options.enum_by_index)?, q)
+            )),
+            CUSTOM(emit) => emit(&db_name, eq, val),
+            LTREE if eq == CompOp::Equal => {
+                Ok(format!("{db_name} <@ {q}{}{q}", ltree_checker(val, false)?))
+            }
+            LTREE => Ok(format!("{db_name}{eq}{q}{}{q}", ltree_checker(val, false)?)),
+            TSVECTOR(config) if eq == CompOp::Equal => tsquery(&db_name, config, val, q),
+            TSVECTOR(_) => Err(ParseError(format!("No full-text search with '{eq}'"))),
             BOOL => {
                 let not = try_bool(val)? == (eq == CompOp::Equal);
                 Ok(format!("{db_name}{}", if not { "" } else { "=false" }))
             }
-            NUMERIC(_, _) | INTEGER(_, _) => Ok(format!("{db_name}{eq}{}", db_type.sql_safe(val)?)),
-            DATE => {
-                let date = date_matcher(Doy::today(), d, val).map(|d| d.start())?;
-                Ok(format!("{db_name}{eq}'{date:#}'"))
+            NUMERIC(_, scale) if options.round_to_scale => Ok(format!(
+                "{db_name}{eq}{}",
+                db_type.sql_safe(&round_to_scale(val, *scale), q)?
+            )),
+            NUMERIC(_, _) | INTEGER(_, _) => {
+                Ok(format!("{db_name}{eq}{}", db_type.sql_safe(val, q)?))
+            }
+            TIMESTAMP | TIMESTAMPTZ(_) if self.time_of_day => {
+                Ok(format!("{db_name}{eq}{q}{}{q}", time_checker(val)?))
             }
-            _ => Ok(format!("{db_name}{eq}'{}'", db_type.sql_safe(val)?)),
+            TIMESTAMPTZ(zone) => Ok(format!(
+                "{db_name}{eq}{q}{} {zone}{q}",
+                db_type.sql_safe(val, q)?
+            )),
+            ARRAY(inner @ (INTEGER(_, _) | NUMERIC(_, _))) if eq == CompOp::Equal => Ok(format!(
+                "{} = ANY({db_name})",
+                inner.sql_safe(&options.number(val), q)?
+            )),
+            ARRAY(inner) if eq == CompOp::Equal => Ok(format!(
+                "{q}{}{q} = ANY({db_name})",
+                inner.sql_safe(val, q)?
+            )),
+            ARRAY(_) => Err(ParseError(format!("No list search with '{eq}'"))),
+            DATE => Ok(format!("{db_name}{eq}{q}{}{q}", self.date_value(val, d)?)),
+            _ => Ok(format!("{db_name}{eq}{q}{}{q}", db_type.sql_safe(val, q)?)),
         }
     }
 
     /// Transforms the given `val` into a LIKE-expression. Replaces key-symbols from glob-style to
     /// form a sql-save query.
-    pub(crate) fn try_sql_like(&self, val: &str) -> Result<String, SuchError> {
-        let Self {
-            db_name, db_type, ..
-        } = self;
+    pub(crate) fn try_sql_like(
+        &self,
+        val: &str,
+        options: &SuchOptions,
+        table: Option<&str>,
+    ) -> Result<String, SuchError> {
+        self.check_len(val)?;
+        let db_type = self.search_type();
+        let q = options.value_quote.char();
+        let db_name = self.quoted_column(table, q);
         match db_type {
-            VARCHAR(_) | TEXT => Ok(format!("{db_name} LIKE '{}'", db_type.sql_safe(val)?)),
-            DATE | TIMESTAMP => Err(SuchError::LikeNotPossible),
-            _ => Ok(format!("{db_name}::TEXT LIKE '{}'", db_type.sql_safe(val)?)),
+            VARCHAR(_) | TEXT if options.unaccent => Ok(format!(
+                "unaccent({db_name}) ILIKE unaccent({q}{}{q})",
+                db_type.sql_safe(val, q)?
+            )),
+            VARCHAR(_) | TEXT => Ok(format!(
+                "{db_name} LIKE {q}{}{q}",
+                db_type.sql_safe(val, q)?
+            )),
+            DATE | TIMESTAMP | TIMESTAMPTZ(_) => Err(SuchError::LikeNotPossible),
+            CUSTOM(emit) => emit(&db_name, CompOp::Equal, val),
+            LTREE => Ok(format!("{db_name} ~ {q}{}{q}", ltree_checker(val, true)?)),
+            TSVECTOR(config) => tsquery(&db_name, config, val, q),
+            JSONB if self.json_path.is_none() => Err(self.missing_json_path()),
+            JSONB => Ok(format!("{db_name} LIKE {q}{}{q}", TEXT.sql_safe(val, q)?)),
+            ARRAY(inner) => {
+                let element = if matches!(inner, VARCHAR(_) | TEXT) {
+                    "t"
+                } else {
+                    "t::TEXT"
+                };
+                Ok(format!(
+                    "EXISTS (SELECT 1 FROM unnest({db_name}) t WHERE {element} LIKE {q}{}{q})",
+                    inner.sql_safe(val, q)?
+                ))
+            }
+            _ => Ok(format!(
+                "{db_name}::TEXT LIKE {q}{}{q}",
+                db_type.sql_safe(val, q)?
+            )),
+        }
+    }
+
+    /// `val` as the date or timestamp compared in the SQL: a date like `heute` or `1.1.2020` as
+    /// `YYYY-MM-DD`, the start of the range it names in the `direction`, a timestamp completed
+    /// to `YYYY-MM-DD HH:MM:SS`. Values of other fields are returned as they are.
+    ///
+    /// # Errors
+    /// Fails on values not being a date.
+    pub(crate) fn date_value(&self, val: &str, direction: Direction) -> Result<String, SuchError> {
+        match self.search_type() {
+            DATE => Ok(format!(
+                "{:#}",
+                date_matcher(Doy::today(), direction, val)?.start()
+            )),
+            TIMESTAMP | TIMESTAMPTZ(_) => timestamp_checker(val.to_string()),
+            _ => Ok(val.to_string()),
+        }
+    }
+
+    /// `val` as a SQL-literal of this field, taken as is: no wildcards, no date-ranges. A string
+    /// is quoted by `q`.
+    ///
+    /// # Errors
+    /// Fails if `val` doesn't fit a numeric field.
+    pub(crate) fn sql_literal(&self, val: &str, q: char) -> Result<String, SuchError> {
+        self.check_len(val)?;
+        match self.search_type() {
+            db_type @ (INTEGER(_, _) | NUMERIC(_, _)) if !val.contains('%') => {
+                db_type.checker(val.to_string(), val)
+            }
+            INTEGER(_, _) | NUMERIC(_, _) => Err(ParseError(format!("No Numeric value '{val}'"))),
+            _ => Ok(format!("{q}{}{q}", escape_quote(val, q))),
+        }
+    }
+
+    /// The column name, qualified by its own table or else by `table`, if given. A `JSONB` field
+    /// with a path gets the accessor of the path, the time of day of a `TIMESTAMP` field the cast
+    /// to `time`, in the zone of a `TIMESTAMPTZ` field.
+    pub(crate) fn column(&self, table: Option<&str>) -> String {
+        self.quoted_column(table, '\'')
+    }
+
+    /// Like `column`, but the keys of a path and the time zone are quoted by `q`.
+    pub(crate) fn quoted_column(&self, table: Option<&str>, q: char) -> String {
+        let column = match (self.expr, self.table.or(table)) {
+            (Some(expr), _) => format!("({expr})"),
+            (None, Some(table)) => format!("{table}.{}", self.db_name),
+            (None, None) => self.db_name.to_string(),
+        };
+        match &self.json_path {
+            Some(path) => json_accessor(&column, path.as_str(), q),
+            None if self.time_of_day => match self.search_type() {
+                TIMESTAMPTZ(zone) => format!("({column} AT TIME ZONE {q}{zone}{q})::time"),
+                _ => format!("{column}::time"),
+            },
+            None => column,
+        }
+    }
+
+    fn missing_json_path(&self) -> SuchError {
+        let name = self.alias.first().unwrap_or(&self.db_name);
+        ParseError(format!("Field '{name}' needs a path like '{name}.key'"))
+    }
+
+    /// The name of this field in the search-language: the first alias and the json-path.
+    fn text_name(&self) -> String {
+        let name = self.alias.first().unwrap_or(&self.db_name);
+        match &self.json_path {
+            Some(path) => format!("{name}.{}", path.as_str()),
+            None if self.time_of_day => format!("{name}.time"),
+            None => (*name).to_string(),
+        }
+    }
+
+    /// Transforms the given `val` into a regex-match. The value is taken as is, only quotes are
+    /// escaped.
+    ///
+    /// # Errors
+    /// Fails for all but text-fields.
+    pub(crate) fn try_sql_regex(
+        &self,
+        eq: CompOp,
+        val: &str,
+        options: &SuchOptions,
+        table: Option<&str>,
+    ) -> Result<String, SuchError> {
+        self.check_len(val)?;
+        if self.is_text() {
+            let q = options.value_quote.char();
+            let db_name = self.quoted_column(table, q);
+            Ok(format!("{db_name} {eq} {q}{}{q}", escape_quote(val, q)))
+        } else {
+            Err(ParseError(format!("No regex-search in '{}'", self.db_name)))
+        }
+    }
+
+    /// Transforms the given `val` into a comparison of how it sounds, `Meyer` finds `Maier`.
+    /// Emits `soundex(field) = soundex('..')`, which needs PostgreSQL with the `fuzzystrmatch`
+    /// extension installed (`CREATE EXTENSION fuzzystrmatch;`).
+    ///
+    /// # Errors
+    /// Fails for all but text-fields.
+    pub(crate) fn try_sql_similar(
+        &self,
+        eq: CompOp,
+        val: &str,
+        options: &SuchOptions,
+        table: Option<&str>,
+    ) -> Result<String, SuchError> {
+        self.check_len(val)?;
+        if self.is_text() {
+            let q = options.value_quote.char();
+            let db_name = self.quoted_column(table, q);
+            let op = if eq == CompOp::NotSimilar { "<>" } else { "=" };
+            let val = escape_quote(val, q);
+            Ok(format!("soundex({db_name}){op}soundex({q}{val}{q})"))
+        } else {
+            Err(ParseError(format!(
+                "No similarity-search in '{}'",
+                self.db_name
+            )))
         }
     }
 
     #[must_use]
     pub fn is_text(&self) -> bool {
-        matches!(self.db_type, TEXT | VARCHAR(_))
+        matches!(self.search_type(), TEXT | VARCHAR(_))
+    }
+
+    #[must_use]
+    pub fn is_numeric(&self) -> bool {
+        matches!(self.search_type(), INTEGER(_, _) | NUMERIC(_, _))
+    }
+
+    /// Renders a comparison of this field with `val` in the search-language, using the first alias.
+    #[must_use]
+    pub fn as_text(&self, style: Style, op: CompOp, val: &str) -> String {
+        let name = self.text_name();
+        let val = quote_value(val);
+        match style {
+            Style::Compact => format!("{name}{op}{val}"),
+            Style::Pretty => format!("{name} {op} {val}"),
+            Style::Html => format!(
+                "{} {} {}",
+                span("field_name", &name),
+                span("eq", &op.to_string()),
+                span("value", &val)
+            ),
+            Style::Markdown => format!("`{name}` {op} {}", escape_markdown(&val)),
+        }
     }
 
     /// Returns all aliases by which this `DbField` can be used.
@@ -110,91 +720,295 @@ impl DbField {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+/// Signature of the emitter of a `DbType::CUSTOM` field: gets the `db_name`, the comparison
+/// and the value as typed by the user (glob-style wildcards included) and returns the
+/// SQL-expression. The emitter is responsible for escaping the value!
+pub type CustomSql = fn(&str, CompOp, &str) -> Result<String, SuchError>;
+
+/// Signature of the value transformer of a field: gets the value as typed by the user and returns
+/// the value to compare with, see `DbField::with_transformer`.
+pub type Transformer = fn(&str) -> Result<String, SuchError>;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+// emitters compare by address, the same function may rarely compare unequal to itself
+#[allow(unpredictable_function_pointer_comparisons)]
 pub enum DbType {
     VARCHAR(usize),
     TEXT,
-    INTEGER(u64, u64),
+    /// An integer from the minimum to the maximum given, both inclusive and maybe negative.
+    INTEGER(i64, i64),
     NUMERIC(u32, u32),
     BOOL,
     DATE,
     TIMESTAMP,
+    /// A Postgres `timestamptz`, values are taken in the given IANA zone, like `Europe/Berlin`:
+    /// `changed>=2022-12-24` emits `changed>='2022-12-24 00:00:00 Europe/Berlin'`, right on
+    /// both sides of a change to daylight saving time.
+    TIMESTAMPTZ(&'static str),
+    /// A field with its own SQL-operators, e.g. `&&` for geometries or `@@` for full-text.
+    CUSTOM(CustomSql),
+    /// A Postgres `ltree`, `cat=a.b` searches the descendants of `a.b`, `cat=a.*` is a `lquery`.
+    LTREE,
+    /// A Postgres `jsonb`, searched with a path: `meta.color=red` emits `meta->>'color'='red'`.
+    /// Comparisons like `meta.size>5` are numeric and emit `(meta->>'size')::numeric>5`.
+    JSONB,
+    /// Full-text search using the given text-search config, like `english`. Searches emit
+    /// `to_tsvector(..) @@ plainto_tsquery(..)`, which can use a GIN-index.
+    TSVECTOR(&'static str),
+    /// A UUID, searched by equality. Partial ids need a wildcard like `67e55044*`.
+    UUID,
+    /// One of the given values, e.g. a status. Values are matched case-insensitive and emitted as
+    /// given here.
+    ENUM(&'static [&'static str]),
+    /// A Postgres array of the given type, like `text[]`: `tags=sale` emits `'sale' = ANY(tags)`,
+    /// `tags=sa*` searches the elements with LIKE.
+    ARRAY(&'static DbType),
 }
 
 impl DbType {
-    fn sql_safe(&self, val: &str) -> Result<String, SuchError> {
-        let escaper = |c: char| match c {
-            '?' => String::from("_"),
-            '*' => String::from("%"),
-            '\'' => String::from("''"),
-            '_' | '%' => format!("\\{c}"),
-            _ => String::from(c),
-        };
-        self.checker(val.chars().map(escaper).collect::<String>())
+    /// Translates the glob-style wildcards of `val` to SQL and escapes the rest, the `quote` like
+    /// `escape_quote`. A wildcard escaped by a backslash (`A\*B`) is taken literally.
+    fn sql_safe(&self, val: &str, quote: char) -> Result<String, SuchError> {
+        let mut escaped = String::with_capacity(val.len());
+        let mut chars = val.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next_if(|n| matches!(n, '*' | '?')) {
+                    Some(n) => escaped.push(n),
+                    None => escaped.push_str(&escape_quote("\\", quote)),
+                },
+                '?' => escaped.push('_'),
+                '*' => escaped.push('%'),
+                c if c == quote => {
+                    escaped.push(c);
+                    escaped.push(c);
+                }
+                '_' | '%' => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                _ => escaped.push(c),
+            }
+        }
+        self.checker(escaped, val)
     }
 
-    fn checker(&self, val: String) -> Result<String, SuchError> {
-        use std::str::FromStr;
+    /// Checks the escaped `val`. Errors show the `input` as typed by the user.
+    fn checker(&self, val: String, input: &str) -> Result<String, SuchError> {
         match self {
-            VARCHAR(a) if val.len() > *a => Err(ParseError(format!("Value: '{val}' to long"))),
+            VARCHAR(a) if val.len() > *a => Err(ParseError(format!("Value: '{input}' to long"))),
             VARCHAR(_) | TEXT => Ok(val),
-            TIMESTAMP => timestamp_checker(val),
+            TIMESTAMP | TIMESTAMPTZ(_) => timestamp_checker(val),
+            UUID => uuid_checker(val, input),
+            ENUM(values) => enum_checker(values, input, false),
             INTEGER(min, max) => {
                 let c_val = val.replace(',', ".");
-                match u64::from_str(&c_val.replace('%', "")) {
-                    Ok(d) if d <= *max && d >= *min => Ok(c_val),
-                    _ => Err(ParseError(format!("No Integer value '{val}'"))),
+                match i64::from_str(&c_val.replace('%', "")) {
+                    Ok(d) if d <= *max && d >= *min && !c_val.starts_with('+') => {
+                        Ok(strip_leading_zeros(c_val))
+                    }
+                    _ => Err(ParseError(format!("No Integer value '{input}'"))),
                 }
             }
-            NUMERIC(len, _) => {
+            NUMERIC(precision, scale) => {
                 let c_val = val.replace(',', ".");
                 let number = c_val.replace('%', "");
+                // a LIKE-pattern is only part of a number
+                let fits = c_val.contains('%') || fits_numeric(&number, *precision, *scale);
                 match f64::from_str(&number) {
-                    Ok(_) if number.len() < (len + 1) as usize => Ok(c_val),
-                    _ => Err(ParseError(format!("No Numeric value '{val}'"))),
+                    Ok(_) if fits && !number.starts_with('+') => Ok(strip_leading_zeros(c_val)),
+                    _ => Err(ParseError(format!("No Numeric value '{input}'"))),
                 }
             }
             _ => Err(ParseError(format!(
-                "Don't know how to handle: {self:?} = '{val}'"
+                "Don't know how to handle: {self:?} = '{input}'"
             ))),
         }
     }
 
+    /// Default for the longest value accepted in a field of this type.
+    #[must_use]
+    pub fn max_len(&self) -> usize {
+        match self {
+            VARCHAR(len) => *len,
+            BOOL => 8,
+            INTEGER(_, _) | NUMERIC(_, _) => 32,
+            DATE => 20,
+            TIMESTAMP | TIMESTAMPTZ(_) => 32,
+            UUID => 36,
+            ENUM(values) => values
+                .iter()
+                .map(|v| v.chars().count())
+                .max()
+                .unwrap_or(0)
+                .max(8),
+            LTREE => 256,
+            ARRAY(inner) => inner.max_len(),
+            TEXT | TSVECTOR(_) | JSONB | CUSTOM(_) => 200,
+        }
+    }
+
     #[must_use]
     pub fn name(&self) -> String {
         match self {
             VARCHAR(_) | TEXT => "TEXT",
             INTEGER(_, _) | NUMERIC(_, _) => "NUMBER",
             BOOL => "BOOL",
-            DATE | TIMESTAMP => "TIME",
+            DATE | TIMESTAMP | TIMESTAMPTZ(_) => "TIME",
+            CUSTOM(_) => "CUSTOM",
+            LTREE => "PATH",
+            TSVECTOR(_) => "TEXT",
+            UUID => "ID",
+            JSONB => "JSON",
+            ENUM(_) => "ENUM",
+            ARRAY(_) => "LIST",
         }
         .into()
     }
 }
 
-#[derive(Debug)]
+/// The type as written in SQL, like `VARCHAR(18)` or `NUMERIC(12,2)`, parsed back by `FromStr`.
+/// For the simplified type of a help see `DbType::name`.
+impl Display for DbType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VARCHAR(len) => write!(f, "VARCHAR({len})"),
+            TEXT => f.write_str("TEXT"),
+            INTEGER(i64::MIN, i64::MAX) => f.write_str("INTEGER"),
+            INTEGER(min, max) => write!(f, "INTEGER({min},{max})"),
+            NUMERIC(precision, scale) => write!(f, "NUMERIC({precision},{scale})"),
+            BOOL => f.write_str("BOOL"),
+            DATE => f.write_str("DATE"),
+            TIMESTAMP => f.write_str("TIMESTAMP"),
+            TIMESTAMPTZ(zone) => write!(f, "TIMESTAMPTZ({zone})"),
+            CUSTOM(_) => f.write_str("CUSTOM"),
+            LTREE => f.write_str("LTREE"),
+            JSONB => f.write_str("JSONB"),
+            TSVECTOR(config) => write!(f, "TSVECTOR({config})"),
+            UUID => f.write_str("UUID"),
+            ENUM(values) => write!(f, "ENUM({})", values.join(",")),
+            ARRAY(element) => write!(f, "ARRAY({element})"),
+        }
+    }
+}
+
+/// Parses a type as written in a config, like `VARCHAR(18)`, `NUMERIC(12,2)`, `INTEGER(0,150)` or
+/// `TEXT`, case-insensitive. `INTEGER` alone takes the range of an `i64`, `ARRAY(TEXT)` an element
+/// type without parameters. Types referring to static data, like the zone of a `TIMESTAMPTZ` or
+/// the values of an `ENUM`, have to be constructed in code.
+impl FromStr for DbType {
+    type Err = SuchError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn number<T: FromStr>(arg: &str, s: &str) -> Result<T, SuchError> {
+            arg.parse()
+                .map_err(|_| ParseError(format!("'{s}' is no DbType!")))
+        }
+        let no_type = || ParseError(format!("'{s}' is no DbType!"));
+        let trimmed = s.trim();
+        let (name, args) = match trimmed.split_once('(') {
+            Some((name, rest)) => {
+                let args = rest.strip_suffix(')').ok_or_else(no_type)?;
+                (
+                    name.trim(),
+                    Some(args.split(',').map(str::trim).collect::<Vec<_>>()),
+                )
+            }
+            None => (trimmed, None),
+        };
+        match (name.to_ascii_uppercase().as_str(), args.as_deref()) {
+            ("VARCHAR", Some([len])) => Ok(VARCHAR(number(len, s)?)),
+            ("TEXT", None) => Ok(TEXT),
+            ("INTEGER", None) => Ok(INTEGER(i64::MIN, i64::MAX)),
+            ("INTEGER", Some([min, max])) => match (number(min, s)?, number(max, s)?) {
+                (min, max) if min <= max => Ok(INTEGER(min, max)),
+                _ => Err(no_type()),
+            },
+            ("NUMERIC", Some([precision, scale])) => {
+                match (number(precision, s)?, number(scale, s)?) {
+                    (precision, scale) if scale <= precision => Ok(NUMERIC(precision, scale)),
+                    _ => Err(no_type()),
+                }
+            }
+            ("BOOL", None) => Ok(BOOL),
+            ("DATE", None) => Ok(DATE),
+            ("TIMESTAMP", None) => Ok(TIMESTAMP),
+            ("LTREE", None) => Ok(LTREE),
+            ("JSONB", None) => Ok(JSONB),
+            ("UUID", None) => Ok(UUID),
+            ("ARRAY", Some([element])) => match element.parse()? {
+                TEXT => Ok(ARRAY(&TEXT)),
+                INTEGER(i64::MIN, i64::MAX) => Ok(ARRAY(&INTEGER(i64::MIN, i64::MAX))),
+                BOOL => Ok(ARRAY(&BOOL)),
+                DATE => Ok(ARRAY(&DATE)),
+                TIMESTAMP => Ok(ARRAY(&TIMESTAMP)),
+                LTREE => Ok(ARRAY(&LTREE)),
+                JSONB => Ok(ARRAY(&JSONB)),
+                UUID => Ok(ARRAY(&UUID)),
+                _ => Err(ParseError(format!(
+                    "'{s}' needs an element type without parameters"
+                ))),
+            },
+            ("TIMESTAMPTZ" | "TSVECTOR" | "ENUM" | "CUSTOM", _) => Err(ParseError(format!(
+                "'{s}' refers to static data, construct it in code"
+            ))),
+            _ => Err(no_type()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct SortField {
     pub desc: bool,
     pub field: DbField,
 }
 
 impl SortField {
-    pub fn to_sql(&self) -> String {
-        format!(
-            "{}{}",
-            self.field.db_name,
-            if self.desc { " DESC" } else { "" }
-        )
+    pub fn to_sql(&self, options: &SuchOptions) -> String {
+        let db_name = self
+            .field
+            .quoted_column(options.table_prefix, options.value_quote.char());
+        let desc = if self.desc { " DESC" } else { "" };
+        if options.case_insensitive_sort && self.field.is_text() {
+            format!("LOWER({db_name}){desc}")
+        } else {
+            format!("{db_name}{desc}")
+        }
+    }
+
+    /// Renders this sort field in the given `style`.
+    #[must_use]
+    pub fn as_text(&self, style: Style) -> String {
+        let name = self.field.text_name();
+        match (style, self.desc) {
+            (Style::Compact, true) => format!("^{name}"),
+            (Style::Pretty, true) => format!("{name} descending"),
+            (Style::Compact | Style::Pretty, false) => name,
+            (Style::Html, true) => {
+                format!(
+                    "{} {}",
+                    span("field_name", &name),
+                    span("down", "descending")
+                )
+            }
+            (Style::Html, false) => span("field_name", &name),
+            (Style::Markdown, true) => format!("`{name}` descending"),
+            (Style::Markdown, false) => format!("`{name}`"),
+        }
     }
 }
 
 #[cfg(test)]
 mod should {
     use crate::comp_op::CompOp;
-    use crate::db_field::DbField;
-    use crate::db_field::DbType::{BOOL, DATE, INTEGER, VARCHAR};
+    use crate::db_field::DbType::{ARRAY, BOOL, DATE, INTEGER, NUMERIC, TEXT, VARCHAR};
+    use crate::db_field::{DbField, DbType, SortField};
+    use crate::error::SuchError;
+    use crate::sql_term::SQLTerm;
     use crate::sql_term::SQLTerm::{AND, LIKE, NOT, OR, VALUE};
-    use crate::DbType::TIMESTAMP;
+    use crate::style::Style;
+    use crate::suchbar::{SuchOptions, ValueQuote};
+    use crate::DbType::{CUSTOM, ENUM, JSONB, LTREE, TIMESTAMP, TIMESTAMPTZ, TSVECTOR, UUID};
     use timewarp::Direction::From;
 
     const ARTIKEL: DbField = DbField::new(
@@ -208,8 +1022,46 @@ mod should {
         DbField::new("end_date", DATE, "READ_OFFER", &["enddate", "end_date"]);
     const NAME: DbField = DbField::new("ma_active", VARCHAR(32), "READ_OFFER", &["akt"]);
     const PRICE: DbField = DbField::new("price", INTEGER(0, 2000), "READ_OFFER_PRICE", &["price"]);
+    const AMOUNT: DbField = DbField::new("amount", NUMERIC(10, 2), "READ_OFFER", &["amount"]);
     const CHANGED: DbField =
         DbField::new("changed", TIMESTAMP, "READ_OFFER", &["changed", "updated"]);
+    const CATEGORY: DbField = DbField::new("cat", LTREE, "READ_OFFER", &["cat"]);
+    const STATUS: DbField = DbField::new(
+        "status",
+        ENUM(&["active", "in_progress", "closed"]),
+        "READ_OFFER",
+        &["status"],
+    );
+    const FULL_NAME: DbField = DbField::computed(
+        "full_name",
+        "first || ' ' || last",
+        TEXT,
+        "READ_OFFER",
+        &["name"],
+    );
+    const META: DbField = DbField::new("meta", JSONB, "READ_OFFER", &["meta"]);
+    const ID: DbField = DbField::new("id", UUID, "READ_OFFER", &["id"]);
+    const DESCRIPTION: DbField = DbField::new(
+        "positionstext",
+        TSVECTOR("english"),
+        "READ_OFFER",
+        &["desc"],
+    );
+    const AREA: DbField = DbField::new("geom", CUSTOM(bounding_box), "READ_OFFER", &["area"]);
+
+    fn bounding_box(db_name: &str, eq: CompOp, val: &str) -> Result<String, SuchError> {
+        let coords = val
+            .split(',')
+            .map(|c| c.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| SuchError::ParseError(format!("No bounding box: '{val}'")))?;
+        match (eq, coords.as_slice()) {
+            (CompOp::Equal, [x1, y1, x2, y2]) => Ok(format!(
+                "{db_name} && ST_MakeEnvelope({x1}, {y1}, {x2}, {y2})"
+            )),
+            _ => Err(SuchError::ParseError(format!("No bounding box: '{val}'"))),
+        }
+    }
 
     #[test]
     fn op_to_sql() {
@@ -246,4 +1098,638 @@ mod should {
         let df = VALUE(CHANGED, CompOp::Lt, From, "2022-09-01 23:30:00 MEZ".into());
         assert!(df.to_sql().is_err());
     }
+
+    #[test]
+    fn timestamptz_to_sql() {
+        const CHANGED_TZ: DbField = DbField::new(
+            "changed",
+            TIMESTAMPTZ("Europe/Berlin"),
+            "READ_OFFER",
+            &["changed"],
+        );
+        let df = VALUE(CHANGED_TZ, CompOp::Gte, From, "2022-12-24".into());
+        assert_eq!(
+            "changed>='2022-12-24 00:00:00 Europe/Berlin'",
+            df.to_sql().expect("This should not panic!")
+        );
+        let df = VALUE(CHANGED_TZ, CompOp::Lt, From, "2022-03-27 02:30:00".into());
+        assert_eq!(
+            "changed<'2022-03-27 02:30:00 Europe/Berlin'",
+            df.to_sql().expect("This should not panic!")
+        );
+        let df = VALUE(
+            CHANGED_TZ
+                .with_path("time")
+                .expect("This should not panic!"),
+            CompOp::Gte,
+            From,
+            "9".into(),
+        );
+        assert_eq!(
+            "(changed AT TIME ZONE 'Europe/Berlin')::time>='09:00:00'",
+            df.to_sql().expect("This should not panic!")
+        );
+        assert!(LIKE(CHANGED_TZ, "2022*".into()).to_sql().is_err());
+    }
+
+    #[test]
+    fn transformer_to_sql() {
+        fn yes_no(val: &str) -> Result<String, SuchError> {
+            match val.to_lowercase().as_str() {
+                "ja" | "yes" => Ok(String::from("Y")),
+                "nein" | "no" => Ok(String::from("N")),
+                _ => Err(SuchError::ParseError(format!("No yes or no: '{val}'"))),
+            }
+        }
+        const ACTIVE: DbField =
+            DbField::new("aktiv", VARCHAR(1), "READ_OFFER", &["aktiv"]).with_transformer(yes_no);
+        let df = VALUE(ACTIVE, CompOp::Equal, From, "Ja".into());
+        assert_eq!("aktiv='Y'", df.to_sql().expect("This should not panic!"));
+        let df = VALUE(ACTIVE, CompOp::NotEqual, From, "nein".into());
+        assert_eq!("aktiv!='N'", df.to_sql().expect("This should not panic!"));
+        let df = VALUE(ACTIVE, CompOp::Equal, From, "vielleicht".into());
+        assert!(df.to_sql().is_err());
+    }
+
+    #[test]
+    fn custom_to_sql() {
+        let df = VALUE(AREA, CompOp::Equal, From, "8.1,53.0,8.3,53.2".into());
+        assert_eq!(
+            df.to_sql().unwrap_or_default(),
+            "geom && ST_MakeEnvelope(8.1, 53, 8.3, 53.2)"
+        );
+        let df = NOT(Box::new(VALUE(AREA, CompOp::Equal, From, "1,2,3,4".into())));
+        assert_eq!(
+            df.to_sql().unwrap_or_default(),
+            "NOT geom && ST_MakeEnvelope(1, 2, 3, 4)"
+        );
+        let df = VALUE(AREA, CompOp::Gt, From, "1,2,3,4".into());
+        assert!(df.to_sql().is_err());
+        let df = VALUE(AREA, CompOp::Equal, From, "1,2,3".into());
+        assert!(df.to_sql().is_err());
+    }
+
+    #[test]
+    fn ltree_to_sql() {
+        let df = VALUE(CATEGORY, CompOp::Equal, From, "electronics.phones".into());
+        assert_eq!(
+            df.to_sql().unwrap_or_default(),
+            "cat <@ 'electronics.phones'"
+        );
+        let df = LIKE(CATEGORY, "electronics.*".into());
+        assert_eq!(df.to_sql().unwrap_or_default(), "cat ~ 'electronics.*'");
+        let df = LIKE(CATEGORY, "*.phones.*".into());
+        assert_eq!(df.to_sql().unwrap_or_default(), "cat ~ '*.phones.*'");
+
+        let df = VALUE(CATEGORY, CompOp::Equal, From, "electronics..phones".into());
+        assert!(df.to_sql().is_err());
+        let df = VALUE(CATEGORY, CompOp::Equal, From, "it's.phones".into());
+        assert!(df.to_sql().is_err());
+        let df = LIKE(CATEGORY, "electronics.ph*".into());
+        assert!(df.to_sql().is_err());
+    }
+
+    #[test]
+    fn signed_integer() {
+        const TEMPERATURE: DbField =
+            DbField::new("temperature", INTEGER(-10, 10), "READ_OFFER", &["temp"]);
+        let sql = |val: &str| VALUE(TEMPERATURE, CompOp::Gt, From, val.into()).to_sql();
+        assert_eq!("temperature>-5", sql("-5").expect("This should not panic!"));
+        assert_eq!(
+            "temperature>-10",
+            sql("-010").expect("This should not panic!")
+        );
+        assert_eq!("temperature>10", sql("10").expect("This should not panic!"));
+        assert!(sql("-11").is_err());
+        assert!(sql("11").is_err());
+        assert!(VALUE(PRICE, CompOp::Gt, From, "-5".into())
+            .to_sql()
+            .is_err());
+    }
+
+    #[test]
+    fn array_to_sql() {
+        const TAGS: DbField = DbField::new("tags", ARRAY(&TEXT), "READ_OFFER", &["tags"]);
+        const SIZES: DbField =
+            DbField::new("sizes", ARRAY(&INTEGER(0, 100)), "READ_OFFER", &["sizes"]);
+        let sql = |term: SQLTerm| term.to_sql().expect("This should not panic!");
+        assert_eq!(
+            "'sale' = ANY(tags)",
+            sql(VALUE(TAGS, CompOp::Equal, From, "sale".into()))
+        );
+        assert_eq!(
+            "NOT 'it''s' = ANY(tags)",
+            sql(NOT(Box::new(VALUE(
+                TAGS,
+                CompOp::Equal,
+                From,
+                "it's".into()
+            ))))
+        );
+        assert_eq!(
+            "EXISTS (SELECT 1 FROM unnest(tags) t WHERE t LIKE 'sa%')",
+            sql(LIKE(TAGS, "sa*".into()))
+        );
+        assert_eq!(
+            "42 = ANY(sizes)",
+            sql(VALUE(SIZES, CompOp::Equal, From, "042".into()))
+        );
+        assert_eq!(
+            "EXISTS (SELECT 1 FROM unnest(sizes) t WHERE t::TEXT LIKE '4%')",
+            sql(LIKE(SIZES, "4*".into()))
+        );
+        assert!(VALUE(SIZES, CompOp::Equal, From, "101".into())
+            .to_sql()
+            .is_err());
+        assert!(VALUE(TAGS, CompOp::Gt, From, "sale".into())
+            .to_sql()
+            .is_err());
+    }
+
+    #[test]
+    fn parse_and_display_db_types() {
+        let parse = |s: &str| s.parse::<DbType>().expect("This should not panic!");
+        assert_eq!(VARCHAR(18), parse("VARCHAR(18)"));
+        assert_eq!(VARCHAR(18), parse(" varchar ( 18 ) "));
+        assert_eq!(NUMERIC(12, 2), parse("NUMERIC(12,2)"));
+        assert_eq!(NUMERIC(12, 2), parse("Numeric(12, 2)"));
+        assert_eq!(INTEGER(-5, 150), parse("INTEGER(-5,150)"));
+        assert_eq!(INTEGER(i64::MIN, i64::MAX), parse("integer"));
+        assert_eq!(TEXT, parse("text"));
+        assert_eq!(TIMESTAMP, parse("TIMESTAMP"));
+        assert_eq!(UUID, parse("uuid"));
+        assert_eq!(ARRAY(&TEXT), parse("ARRAY(text)"));
+
+        for db_type in [
+            VARCHAR(18),
+            TEXT,
+            INTEGER(-5, 150),
+            INTEGER(i64::MIN, i64::MAX),
+            NUMERIC(12, 2),
+            BOOL,
+            DATE,
+            TIMESTAMP,
+            LTREE,
+            JSONB,
+            UUID,
+            ARRAY(&TEXT),
+            ARRAY(&INTEGER(i64::MIN, i64::MAX)),
+        ] {
+            assert_eq!(db_type, parse(&db_type.to_string()));
+        }
+        assert_eq!("VARCHAR(18)", VARCHAR(18).to_string());
+        assert_eq!("NUMERIC(12,2)", NUMERIC(12, 2).to_string());
+        assert_eq!("INTEGER", INTEGER(i64::MIN, i64::MAX).to_string());
+        assert_eq!("ARRAY(UUID)", ARRAY(&UUID).to_string());
+        assert_eq!(
+            "TIMESTAMPTZ(Europe/Berlin)",
+            TIMESTAMPTZ("Europe/Berlin").to_string()
+        );
+        assert_eq!("ENUM(open,closed)", ENUM(&["open", "closed"]).to_string());
+        assert_eq!("TEXT", VARCHAR(18).name());
+
+        for malformed in [
+            "",
+            "VARCHAR",
+            "VARCHAR(a)",
+            "VARCHAR(18",
+            "VARCHAR(-1)",
+            "TEXT(5)",
+            "NUMERIC(12)",
+            "NUMERIC(2,12)",
+            "INTEGER(150,0)",
+            "BLOB",
+            "ARRAY(VARCHAR(5))",
+            "TIMESTAMPTZ(Europe/Berlin)",
+            "ENUM(a,b)",
+        ] {
+            assert!(malformed.parse::<DbType>().is_err(), "{malformed}");
+        }
+    }
+
+    #[test]
+    fn hash_types_and_fields() {
+        use std::collections::{HashMap, HashSet};
+        let types = [
+            TEXT,
+            VARCHAR(8),
+            VARCHAR(8),
+            NUMERIC(10, 2),
+            ARRAY(&TEXT),
+            ARRAY(&TEXT),
+        ]
+        .into_iter()
+        .collect::<HashSet<_>>();
+        assert_eq!(4, types.len());
+        assert!(types.contains(&NUMERIC(10, 2)));
+        assert!(!types.contains(&NUMERIC(10, 3)));
+
+        let mut hits = HashMap::new();
+        for field in [AMOUNT, PRICE, AMOUNT] {
+            *hits.entry(field).or_insert(0) += 1;
+        }
+        assert_eq!(Some(&2), hits.get(&AMOUNT));
+        assert_eq!(Some(&1), hits.get(&PRICE));
+    }
+
+    #[test]
+    fn strip_leading_zeros() {
+        let df = VALUE(PRICE, CompOp::Equal, From, "007".into());
+        assert_eq!(df.to_sql().unwrap_or_default(), "price=7");
+        let df = VALUE(PRICE, CompOp::Equal, From, "0".into());
+        assert_eq!(df.to_sql().unwrap_or_default(), "price=0");
+        let df = LIKE(PRICE, "*007*".into());
+        assert_eq!(df.to_sql().unwrap_or_default(), "price::TEXT LIKE '%007%'");
+        let df = VALUE(AMOUNT, CompOp::Gt, From, "007,50".into());
+        assert_eq!(df.to_sql().unwrap_or_default(), "amount>7.50");
+        let df = VALUE(AMOUNT, CompOp::Gt, From, "-00.5".into());
+        assert_eq!(df.to_sql().unwrap_or_default(), "amount>-0.5");
+        let df = VALUE(ARTIKEL, CompOp::Equal, From, "007".into());
+        assert_eq!(df.to_sql().unwrap_or_default(), "article='007'");
+    }
+
+    #[test]
+    fn value_quote() {
+        for (quote, eq, like) in [
+            (
+                ValueQuote::Single,
+                r#"article='it''s "x" `y`'"#,
+                r#"article LIKE 'it''s "x" `y`%'"#,
+            ),
+            (
+                ValueQuote::Double,
+                r#"article="it's ""x"" `y`""#,
+                r#"article LIKE "it's ""x"" `y`%""#,
+            ),
+            (
+                ValueQuote::Backtick,
+                r#"article=`it's "x" ``y```"#,
+                r#"article LIKE `it's "x" ``y``%`"#,
+            ),
+        ] {
+            let options = SuchOptions::new().value_quote(quote);
+            let val = r#"it's "x" `y`"#;
+            let df = VALUE(ARTIKEL, CompOp::Equal, From, val.into());
+            assert_eq!(
+                eq,
+                df.to_sql_with(&options, None)
+                    .expect("This should not panic!")
+            );
+            let df = LIKE(ARTIKEL, format!("{val}*"));
+            assert_eq!(
+                like,
+                df.to_sql_with(&options, None)
+                    .expect("This should not panic!")
+            );
+        }
+        for (quote, eq) in [
+            (ValueQuote::Single, r#"article='\" OR 1=1 -- '"#),
+            (ValueQuote::Double, r#"article="\\"" OR 1=1 -- ""#),
+            (ValueQuote::Backtick, r#"article=`\\" OR 1=1 -- `"#),
+        ] {
+            let options = SuchOptions::new().value_quote(quote);
+            let df = VALUE(ARTIKEL, CompOp::Equal, From, r#"\" OR 1=1 -- "#.into());
+            assert_eq!(
+                eq,
+                df.to_sql_with(&options, None)
+                    .expect("This should not panic!")
+            );
+        }
+        let color = META.with_path("color").expect("This should not panic!");
+        for (quote, sql) in [
+            (
+                ValueQuote::Single,
+                [
+                    "article ~ '^it''s'",
+                    "to_tsvector('english', positionstext) @@ plainto_tsquery('english', 'it''s')",
+                    "meta->>'color'='it''s'",
+                    "'it''s'",
+                ],
+            ),
+            (
+                ValueQuote::Double,
+                [
+                    r#"article ~ "^it's""#,
+                    r#"to_tsvector("english", positionstext) @@ plainto_tsquery("english", "it's")"#,
+                    r#"meta->>"color"="it's""#,
+                    r#""it's""#,
+                ],
+            ),
+            (
+                ValueQuote::Backtick,
+                [
+                    "article ~ `^it's`",
+                    "to_tsvector(`english`, positionstext) @@ plainto_tsquery(`english`, `it's`)",
+                    "meta->>`color`=`it's`",
+                    "`it's`",
+                ],
+            ),
+        ] {
+            let options = SuchOptions::new().value_quote(quote);
+            let [regex, tsquery, json, literal] = sql;
+            let sql = |field: &DbField, eq: CompOp, val: &str| {
+                VALUE(field.clone(), eq, From, val.into())
+                    .to_sql_with(&options, None)
+                    .expect("This should not panic!")
+            };
+            assert_eq!(regex, sql(&ARTIKEL, CompOp::Match, "^it's"));
+            assert_eq!(tsquery, sql(&DESCRIPTION, CompOp::Equal, "it's"));
+            assert_eq!(json, sql(&color, CompOp::Equal, "it's"));
+            assert_eq!(
+                literal,
+                ARTIKEL
+                    .sql_literal("it's", quote.char())
+                    .expect("This should not panic!")
+            );
+        }
+        let options = SuchOptions::new().value_quote(ValueQuote::Double);
+        let df = VALUE(PRICE, CompOp::Gt, From, "12".into());
+        assert_eq!(
+            "price>12",
+            df.to_sql_with(&options, None)
+                .expect("This should not panic!")
+        );
+    }
+
+    #[test]
+    fn numeric_precision_and_scale() {
+        let sql = |val: &str| VALUE(AMOUNT, CompOp::Equal, From, val.into()).to_sql();
+        assert_eq!(
+            "amount=12345678.99",
+            sql("12345678.99").expect("This should not panic!")
+        );
+        assert_eq!(
+            "amount=-12345678.99",
+            sql("-12345678,99").expect("This should not panic!")
+        );
+        assert_eq!("amount=5.1", sql("0005.1").expect("This should not panic!"));
+        // over scale
+        assert!(sql("1234.567890").is_err());
+        assert!(sql("0.001").is_err());
+        // over precision
+        assert!(sql("123456789").is_err());
+        assert!(sql("123456789.5").is_err());
+        assert!(sql("1e5").is_err());
+    }
+
+    #[test]
+    fn round_to_scale() {
+        let options = SuchOptions::new().round_to_scale(true);
+        let sql = |val: &str| {
+            VALUE(AMOUNT, CompOp::Lt, From, val.into())
+                .to_sql_with(&options, None)
+                .expect("This should not panic!")
+        };
+        assert_eq!("amount<10.99", sql("10.994"));
+        assert_eq!("amount<11.00", sql("10.995"));
+        assert_eq!("amount<100.00", sql("99,999"));
+        assert_eq!("amount<-0.13", sql("-0.125"));
+        assert_eq!("amount<10.5", sql("10.5"));
+        assert!(VALUE(AMOUNT, CompOp::Lt, From, "10.999".into())
+            .to_sql()
+            .is_err());
+        let like = LIKE(AMOUNT, "10.999*".into()).to_sql_with(&options, None);
+        assert_eq!(
+            "amount::TEXT LIKE '10.999%'",
+            like.expect("This should not panic!")
+        );
+    }
+
+    #[test]
+    fn error_shows_input() {
+        let message = |term: SQLTerm| match term.to_sql() {
+            Err(SuchError::ParseError(msg)) => msg,
+            other => panic!("Unexpected {other:?}"),
+        };
+        assert_eq!(
+            message(LIKE(PRICE, "it's_*".into())),
+            "No Integer value 'it's_*'"
+        );
+        assert_eq!(
+            message(LIKE(NAME, "*Micha's cat with a very long name?".into())),
+            "Value: '*Micha's cat with a very long name?' to long"
+        );
+        assert_eq!(message(LIKE(ID, "it's*".into())), "No UUID: 'it's*'");
+    }
+
+    #[test]
+    fn reject_long_values() {
+        let df = VALUE(
+            ID,
+            CompOp::Equal,
+            From,
+            "67e55044-10b1-426f-9247-bb680e5fe0c8-67e55044".into(),
+        );
+        assert!(df.to_sql().is_err());
+        let df = VALUE(
+            END_DATE,
+            CompOp::Equal,
+            From,
+            "2022-12-24 and a lot more".into(),
+        );
+        assert!(df.to_sql().is_err());
+        let df = VALUE(ACTIVE, CompOp::Equal, From, "true-true-true".into());
+        assert!(df.to_sql().is_err());
+
+        let long = "a".repeat(201);
+        let df = VALUE(FULL_NAME, CompOp::Equal, From, long.clone());
+        assert!(df.to_sql().is_err());
+        let df = VALUE(FULL_NAME.max_len(300), CompOp::Equal, From, long);
+        assert!(df.to_sql().is_ok());
+        let df = VALUE(FULL_NAME.max_len(5), CompOp::Equal, From, "Donald".into());
+        assert!(df.to_sql().is_err());
+    }
+
+    #[test]
+    fn computed_to_sql() {
+        let df = LIKE(FULL_NAME, "Donald D*".into());
+        assert_eq!(
+            df.to_sql().unwrap_or_default(),
+            "(first || ' ' || last) LIKE 'Donald D%'"
+        );
+        let df = VALUE(FULL_NAME, CompOp::Equal, From, "Donald Duck".into());
+        assert_eq!(
+            df.to_sql().unwrap_or_default(),
+            "(first || ' ' || last)='Donald Duck'"
+        );
+        let sort = SortField {
+            desc: true,
+            field: FULL_NAME,
+        };
+        assert_eq!(
+            sort.to_sql(&SuchOptions::new().case_insensitive_sort(true)),
+            "LOWER((first || ' ' || last)) DESC"
+        );
+        assert_eq!(sort.as_text(Style::Compact), "^name");
+    }
+
+    #[test]
+    fn enum_to_sql() {
+        let df = VALUE(STATUS, CompOp::Equal, From, "active".into());
+        assert_eq!(df.to_sql().unwrap_or_default(), "status='active'");
+        let df = VALUE(STATUS, CompOp::NotEqual, From, "In_Progress".into());
+        assert_eq!(df.to_sql().unwrap_or_default(), "status!='in_progress'");
+
+        let df = VALUE(STATUS, CompOp::Equal, From, "open".into());
+        assert!(matches!(
+            df.to_sql(),
+            Err(SuchError::ParseError(msg)) if msg == "'open' is none of: active, in_progress, closed"
+        ));
+        let df = LIKE(STATUS, "act*".into());
+        assert!(df.to_sql().is_err());
+    }
+
+    #[test]
+    fn jsonb_to_sql() {
+        let color = META
+            .with_json_path("color")
+            .expect("This should not panic!");
+        let df = VALUE(color.clone(), CompOp::Equal, From, "red".into());
+        assert_eq!(df.to_sql().unwrap_or_default(), "meta->>'color'='red'");
+        let df = LIKE(color, "*re*".into());
+        assert_eq!(
+            df.to_sql().unwrap_or_default(),
+            "meta->>'color' LIKE '%re%'"
+        );
+        let size = META
+            .with_json_path("dim.width")
+            .expect("This should not panic!");
+        let df = VALUE(size.clone(), CompOp::Gte, From, "2,5".into());
+        assert_eq!(
+            df.to_sql().unwrap_or_default(),
+            "(meta->'dim'->>'width')::numeric>=2.5"
+        );
+        assert_eq!(
+            size.as_text(Style::Compact, CompOp::Gt, "2"),
+            "meta.dim.width>2"
+        );
+
+        let df = VALUE(size, CompOp::Lt, From, "wide".into());
+        assert!(df.to_sql().is_err());
+        let df = VALUE(META, CompOp::Equal, From, "red".into());
+        assert!(df.to_sql().is_err());
+        assert!(PRICE.with_json_path("color").is_err());
+    }
+
+    #[test]
+    fn uuid_to_sql() {
+        let df = VALUE(
+            ID,
+            CompOp::Equal,
+            From,
+            "67e55044-10b1-426f-9247-BB680E5FE0C8".into(),
+        );
+        assert_eq!(
+            df.to_sql().unwrap_or_default(),
+            "id='67e55044-10b1-426f-9247-BB680E5FE0C8'"
+        );
+        let df = VALUE(ID, CompOp::Equal, From, "67e55044*".into());
+        assert_eq!(df.to_sql().unwrap_or_default(), "id::TEXT LIKE '67e55044%'");
+        let df = LIKE(ID, "*-426f-*".into());
+        assert_eq!(df.to_sql().unwrap_or_default(), "id::TEXT LIKE '%-426f-%'");
+
+        let df = VALUE(ID, CompOp::Equal, From, "67e55044".into());
+        assert!(df.to_sql().is_err());
+        let df = VALUE(
+            ID,
+            CompOp::Equal,
+            From,
+            "67e55044-10b1-426f-9247-bb680e5fe0cg".into(),
+        );
+        assert!(df.to_sql().is_err());
+        let df = VALUE(
+            ID,
+            CompOp::Equal,
+            From,
+            "67e5504410b1426f9247bb680e5fe0c8".into(),
+        );
+        assert!(df.to_sql().is_err());
+        let df = LIKE(ID, "it's*".into());
+        assert!(df.to_sql().is_err());
+        assert_eq!(UUID.name(), "ID");
+    }
+
+    #[test]
+    fn tsvector_to_sql() {
+        let df = LIKE(DESCRIPTION, "*some phrase*".into());
+        assert_eq!(
+            df.to_sql().unwrap_or_default(),
+            "to_tsvector('english', positionstext) @@ plainto_tsquery('english', 'some phrase')"
+        );
+        let df = VALUE(DESCRIPTION, CompOp::Equal, From, "Micha's cat".into());
+        assert_eq!(
+            df.to_sql().unwrap_or_default(),
+            "to_tsvector('english', positionstext) @@ plainto_tsquery('english', 'Micha''s cat')"
+        );
+        let df = NOT(Box::new(VALUE(
+            DESCRIPTION,
+            CompOp::Equal,
+            From,
+            "cat".into(),
+        )));
+        assert_eq!(
+            df.to_sql().unwrap_or_default(),
+            "NOT to_tsvector('english', positionstext) @@ plainto_tsquery('english', 'cat')"
+        );
+
+        let df = VALUE(DESCRIPTION, CompOp::Gt, From, "cat".into());
+        assert!(df.to_sql().is_err());
+        let df = LIKE(DESCRIPTION, "**".into());
+        assert!(df.to_sql().is_err());
+    }
+
+    #[test]
+    fn field_as_text() {
+        assert_eq!(
+            ARTIKEL.as_text(Style::Compact, CompOp::Gt, "1245667"),
+            "artnr>1245667"
+        );
+        assert_eq!(
+            ARTIKEL.as_text(Style::Pretty, CompOp::Gt, "1245667"),
+            "artnr > 1245667"
+        );
+        assert_eq!(
+            NAME.as_text(Style::Pretty, CompOp::Equal, "Micha's cat*"),
+            "akt = \"Micha's cat*\""
+        );
+        assert_eq!(
+            NAME.as_text(Style::Html, CompOp::Lte, "<b>"),
+            "<span class=\"field_name\">akt</span> <span class=\"eq\">&lt;=</span> \
+            <span class=\"value\">&quot;&lt;b&gt;&quot;</span>"
+        );
+        assert_eq!(
+            PRICE.as_text(Style::Markdown, CompOp::Gte, "1_000*"),
+            "`price` >= \"1\\_000\\*\""
+        );
+    }
+
+    #[test]
+    fn negated_as_text() {
+        let df = NOT(Box::new(VALUE(
+            ARTIKEL,
+            CompOp::Equal,
+            From,
+            "1245667".into(),
+        )));
+        assert_eq!(df.as_text(Style::Compact).unwrap(), "artnr!=1245667");
+        let df = NOT(Box::new(VALUE(PRICE, CompOp::Lt, From, "100".into())));
+        assert_eq!(df.as_text(Style::Pretty).unwrap(), "price >= 100");
+        let df = NOT(Box::new(LIKE(NAME, "cat*".into())));
+        assert_eq!(df.as_text(Style::Markdown).unwrap(), "`akt` != cat\\*");
+        let df = NOT(Box::new(OR(vec![
+            VALUE(ACTIVE, CompOp::Equal, From, "true".into()),
+            VALUE(END_DATE, CompOp::Gt, From, "2022-12-24".into()),
+        ])));
+        assert_eq!(
+            df.as_text(Style::Compact).unwrap(),
+            "!(akt=true||enddate>2022-12-24)"
+        );
+        assert_eq!(
+            df.as_text(Style::Html).unwrap(),
+            "<span class=\"invert\">NOT</span> ( \
+            <span class=\"field_name\">akt</span> <span class=\"eq\">=</span> <span class=\"value\">true</span> \
+            <span class=\"or\">OR</span> \
+            <span class=\"field_name\">enddate</span> <span class=\"eq\">&gt;</span> <span class=\"value\">2022-12-24</span> )"
+        );
+    }
 }
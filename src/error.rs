@@ -1,12 +1,20 @@
-use crate::error::SuchError::{Denied, LikeNotPossible, ParseError};
+use crate::error::SuchError::{
+    AmbiguousAlias, Denied, EmptyQuery, LikeNotPossible, ParseError, TooDeep, TooLong, UnknownField,
+};
 use crate::suchbar::Rule;
-use std::fmt::{Display, Formatter};
+use core::fmt::{Display, Formatter};
 
 #[derive(Debug)]
 pub enum SuchError {
     ParseError(String),
     LikeNotPossible,
-    Denied,
+    /// The field named first requires the permission named second.
+    Denied(&'static str, &'static str),
+    TooLong,
+    TooDeep,
+    AmbiguousAlias(String, Vec<&'static str>),
+    EmptyQuery,
+    UnknownField(String),
 }
 
 impl From<pest::error::Error<Rule>> for SuchError {
@@ -16,11 +24,20 @@ impl From<pest::error::Error<Rule>> for SuchError {
 }
 
 impl Display for SuchError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             ParseError(str) => write!(f, "{str}"),
             LikeNotPossible => write!(f, "LIKE not possible"),
-            Denied => write!(f, "DENIED"),
+            Denied(field, permission) => {
+                write!(f, "denied: field '{field}' requires {permission}")
+            }
+            TooLong => write!(f, "Query too long"),
+            TooDeep => write!(f, "Query nested too deep"),
+            AmbiguousAlias(alias, fields) => {
+                write!(f, "'{alias}' is ambiguous: {}", fields.join(", "))
+            }
+            EmptyQuery => write!(f, "Query is empty"),
+            UnknownField(name) => write!(f, "Unknown field '{name}'"),
         }
     }
 }
@@ -0,0 +1,122 @@
+use crate::db_field::{DbField, DbType};
+use crate::sql_term::SQLTerm;
+use crate::style::quote_value;
+
+/// What a `Suchbar` understood from a query: one entry per leaf term.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct QueryExplanation {
+    pub terms: Vec<TermExplanation>,
+}
+
+/// A single comparison of the query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TermExplanation {
+    /// Name of the searched column.
+    pub field: &'static str,
+    /// The comparison, like `=`, `>=`, `LIKE` or `NOT LIKE`.
+    pub operator: String,
+    /// The value as given by the user, wildcards in glob-style.
+    pub value: String,
+    /// The user isn't allowed to search this field, the term is ignored.
+    pub denied: bool,
+    /// The values the field accepts, if it's an `ENUM`.
+    pub allowed: &'static [&'static str],
+}
+
+/// A field the user may search, for a help on the search-language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FieldInfo {
+    /// The names of the field in the search-language.
+    pub aliases: Vec<String>,
+    /// The simplified type, like `TEXT` or `NUMBER`.
+    pub db_type: String,
+    /// A term searching the field, like `price>10`.
+    pub example: String,
+    /// The values the field accepts, if it's an `ENUM`.
+    pub allowed: &'static [&'static str],
+}
+
+impl FieldInfo {
+    pub(crate) fn from_field(field: &DbField) -> Self {
+        Self {
+            aliases: field.alias.iter().map(ToString::to_string).collect(),
+            db_type: field.db_type(),
+            example: example(field),
+            allowed: allowed(field),
+        }
+    }
+
+    /// The line of the field in `Suchbar::explanation`, like `[status] ENUM (active, closed)`.
+    pub(crate) fn line(&self) -> String {
+        let line = format!("[{}] {}", self.aliases.join(", "), self.db_type);
+        if self.allowed.is_empty() {
+            line
+        } else {
+            format!("{line} ({})", self.allowed.join(", "))
+        }
+    }
+}
+
+fn example(field: &DbField) -> String {
+    let name = field.alias.first().unwrap_or(&field.db_name);
+    match field.search_type() {
+        DbType::VARCHAR(_) | DbType::TEXT | DbType::TSVECTOR(_) => format!("{name}=Duck*"),
+        DbType::INTEGER(min, _) => format!("{name}>{min}"),
+        DbType::NUMERIC(_, _) => format!("{name}>10,5"),
+        DbType::BOOL => format!("{name}=true"),
+        DbType::DATE | DbType::TIMESTAMP | DbType::TIMESTAMPTZ(_) => format!("{name}>2024-01-01"),
+        DbType::LTREE => format!("{name}=top.sub"),
+        DbType::UUID => format!("{name}=123e4567-e89b-12d3-a456-426614174000"),
+        DbType::JSONB => format!("{name}.key=value"),
+        DbType::ENUM(values) => format!("{name}={}", quote_value(values.first().unwrap_or(&""))),
+        DbType::CUSTOM(_) | DbType::ARRAY(_) => format!("{name}=value"),
+    }
+}
+
+impl QueryExplanation {
+    pub(crate) fn from_term(term: &SQLTerm) -> Self {
+        let mut terms = Vec::new();
+        collect(term, false, &mut terms);
+        Self { terms }
+    }
+}
+
+fn collect(term: &SQLTerm, negated: bool, acc: &mut Vec<TermExplanation>) {
+    use SQLTerm::{AND, DENIED, LIKE, NOT, OR, RAW, VALUE};
+    match term {
+        AND(vec) | OR(vec) => vec.iter().for_each(|t| collect(t, negated, acc)),
+        NOT(inner) => collect(inner, !negated, acc),
+        VALUE(f, eq, _, v) => acc.push(TermExplanation {
+            field: f.db_name,
+            operator: if negated { !*eq } else { *eq }.to_string(),
+            value: v.clone(),
+            denied: false,
+            allowed: allowed(f),
+        }),
+        LIKE(f, v) => acc.push(TermExplanation {
+            field: f.db_name,
+            operator: String::from(if negated { "NOT LIKE" } else { "LIKE" }),
+            value: v.clone(),
+            denied: false,
+            allowed: allowed(f),
+        }),
+        DENIED(f) => acc.push(TermExplanation {
+            field: f.db_name,
+            operator: String::new(),
+            value: String::new(),
+            denied: true,
+            allowed: allowed(f),
+        }),
+        RAW(_) => {}
+    }
+}
+
+fn allowed(field: &DbField) -> &'static [&'static str] {
+    match field.search_type() {
+        DbType::ENUM(values) => values,
+        _ => &[],
+    }
+}
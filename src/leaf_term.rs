@@ -0,0 +1,57 @@
+use crate::db_field::DbField;
+use crate::sql_term::SQLTerm;
+
+/// A single comparison of a query, see `WhereClause::leaves`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LeafTerm<'a> {
+    /// The searched field.
+    pub field: &'a DbField,
+    /// The comparison, like `=`, `>=`, `LIKE` or `NOT LIKE`, negations already applied.
+    pub operator: &'static str,
+    /// The value as given by the user, wildcards in glob-style.
+    pub value: &'a str,
+}
+
+/// Walks the leaf terms of an `SQLTerm` from left to right, denied and raw terms are skipped.
+pub(crate) struct Leaves<'a> {
+    /// Terms still to visit, the next one on top, with their negation.
+    stack: Vec<(&'a SQLTerm, bool)>,
+}
+
+impl<'a> Leaves<'a> {
+    pub(crate) fn new(term: &'a SQLTerm) -> Self {
+        Self {
+            stack: vec![(term, false)],
+        }
+    }
+}
+
+impl<'a> Iterator for Leaves<'a> {
+    type Item = LeafTerm<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use SQLTerm::{AND, DENIED, LIKE, NOT, OR, RAW, VALUE};
+        while let Some((term, negated)) = self.stack.pop() {
+            match term {
+                AND(vec) | OR(vec) => self.stack.extend(vec.iter().rev().map(|t| (t, negated))),
+                NOT(inner) => self.stack.push((inner, !negated)),
+                VALUE(field, eq, _, value) => {
+                    return Some(LeafTerm {
+                        field,
+                        operator: if negated { !*eq } else { *eq }.as_str(),
+                        value,
+                    })
+                }
+                LIKE(field, value) => {
+                    return Some(LeafTerm {
+                        field,
+                        operator: if negated { "NOT LIKE" } else { "LIKE" },
+                        value,
+                    })
+                }
+                DENIED(_) | RAW(_) => {}
+            }
+        }
+        None
+    }
+}
@@ -68,16 +68,42 @@
 //!     }
 //! }
 //! ```
+//!
+//! ## `std`
+//!
+//! The term model (`SQLTerm`, `CompOp`, `SuchError` and the visitor) uses `core` and `alloc`
+//! only and prints nothing. Parsing needs `std`: the alias index is cached process-wide behind
+//! a `RwLock`, and `permeable` and `timewarp` have no `no_std` support. Therefore the crate
+//! can't be built `no_std`.
 
+mod alias_index;
 mod comp_op;
 mod db_field;
 mod error;
+mod explanation;
+mod leaf_term;
+#[cfg(feature = "mongo")]
+mod mongo;
+mod normalize;
+mod query_macro;
 mod sql_term;
+#[cfg(feature = "stemming")]
+mod stemming;
+mod style;
 mod suchbar;
+mod visitor;
 
 #[macro_use]
 extern crate pest_derive;
 
-pub use crate::db_field::{DbField, DbType};
+pub use crate::comp_op::CompOp;
+pub use crate::db_field::{CustomSql, DbField, DbType, Transformer};
+pub use crate::error::SuchError;
+pub use crate::explanation::{FieldInfo, QueryExplanation, TermExplanation};
+pub use crate::leaf_term::LeafTerm;
 pub use crate::sql_term::SQLTerm;
-pub use crate::suchbar::{SuchOptions, Suchbar, WhereClause};
+#[cfg(feature = "stemming")]
+pub use crate::stemming::Stemming;
+pub use crate::style::Style;
+pub use crate::suchbar::{DeniedInAnd, EmptyPolicy, SuchOptions, Suchbar, ValueQuote, WhereClause};
+pub use crate::visitor::SqlTermVisitor;
@@ -0,0 +1,174 @@
+use crate::comp_op::CompOp;
+use crate::db_field::{try_bool, DbField, DbType};
+use crate::error::SuchError;
+use crate::error::SuchError::ParseError;
+use crate::sql_term::{has_wildcard, val_sql, SQLTerm};
+use crate::suchbar::{DeniedInAnd, SuchOptions};
+use crate::visitor::SqlTermVisitor;
+use serde_json::{json, Map, Value};
+use timewarp::Direction;
+
+/// Renders a term as a MongoDB query document, see `WhereClause::to_mongo`. Like in the SQL,
+/// failing terms such as a text searched in a numeric field are left out, and a denied term
+/// fails its group by the `DeniedInAnd`. Raw SQL fails.
+pub(crate) struct MongoVisitor<'a>(pub(crate) &'a SuchOptions);
+
+impl MongoVisitor<'_> {
+    /// Joins the `terms` by `op`, see `explode` for the SQL.
+    fn group(&mut self, op: &str, terms: &[SQLTerm]) -> Result<Value, SuchError> {
+        let mut docs = Vec::with_capacity(terms.len());
+        // the first denied term and the number of denied terms
+        let mut denied = None;
+        let mut denied_count = 0;
+        for term in terms {
+            match term.accept(self) {
+                Ok(doc) => docs.push(doc),
+                // raw SQL is a filter, leaving it out widens the result
+                Err(err) if matches!(term, SQLTerm::RAW(_)) => return Err(err),
+                Err(err @ SuchError::Denied(..)) => {
+                    if op == "$and" && self.0.denied_in_and == DeniedInAnd::Fail {
+                        return Err(err);
+                    }
+                    denied.get_or_insert(err);
+                    denied_count += 1;
+                }
+                Err(_) => {}
+            }
+        }
+        match docs.len() {
+            0 => match denied {
+                Some(err) if denied_count == terms.len() => Err(err),
+                _ => Err(ParseError("Empty SQLTerm!".to_string())),
+            },
+            1 => Ok(docs.remove(0)),
+            _ => Ok(json!({ op: docs })),
+        }
+    }
+
+    /// `val` as a JSON value of the `field`'s type, numbers and booleans unquoted, dates and
+    /// timestamps as compared in the SQL.
+    fn typed(&self, field: &DbField, val: &str, d: Direction) -> Result<Value, SuchError> {
+        Ok(match field.search_type() {
+            DbType::INTEGER(_, _) => val.parse::<i64>().map_or_else(|_| json!(val), Value::from),
+            DbType::NUMERIC(_, _) => self
+                .0
+                .number(val)
+                .parse::<f64>()
+                .map_or_else(|_| json!(val), Value::from),
+            DbType::BOOL => try_bool(val).map_or_else(|_| json!(val), Value::from),
+            DbType::DATE | DbType::TIMESTAMP | DbType::TIMESTAMPTZ(_) => {
+                json!(field.date_value(val, d)?)
+            }
+            _ => json!(val),
+        })
+    }
+}
+
+impl SqlTermVisitor for MongoVisitor<'_> {
+    type Output = Result<Value, SuchError>;
+
+    fn visit_and(&mut self, terms: &[SQLTerm]) -> Self::Output {
+        self.group("$and", terms)
+    }
+
+    fn visit_or(&mut self, terms: &[SQLTerm]) -> Self::Output {
+        self.group("$or", terms)
+    }
+
+    fn visit_not(&mut self, term: &SQLTerm) -> Self::Output {
+        match term {
+            SQLTerm::NOT(inner) => inner.accept(self),
+            SQLTerm::VALUE(f, eq, d, v) => self.visit_value(f, !*eq, *d, v),
+            _ => term.accept(self).map(|doc| json!({ "$nor": [doc] })),
+        }
+    }
+
+    fn visit_value(&mut self, f: &DbField, eq: CompOp, d: Direction, v: &str) -> Self::Output {
+        val_sql(f, eq, v, d, self.0, None)?;
+        if f.time_of_day {
+            return Err(ParseError(format!(
+                "No time of day in MongoDB: '{}'",
+                f.db_name
+            )));
+        }
+        let transformed = f.transformer.map_or(Ok(v.to_string()), |t| t(v))?;
+        let v = transformed.as_str();
+        let like = json!({ "$regex": glob_to_regex(v), "$options": "i" });
+        let cond = match eq {
+            CompOp::Equal if has_wildcard(v) => like,
+            CompOp::NotEqual if has_wildcard(v) => json!({ "$not": like }),
+            CompOp::Equal => self.typed(f, v, d)?,
+            CompOp::NotEqual => json!({ "$ne": self.typed(f, v, d)? }),
+            CompOp::Gt => json!({ "$gt": self.typed(f, v, d)? }),
+            CompOp::Gte => json!({ "$gte": self.typed(f, v, d)? }),
+            CompOp::Lt => json!({ "$lt": self.typed(f, v, d)? }),
+            CompOp::Lte => json!({ "$lte": self.typed(f, v, d)? }),
+            CompOp::Match => json!({ "$regex": v }),
+            CompOp::NotMatch => json!({ "$not": { "$regex": v } }),
+            // Mongo has no soundex, the closest is a case-insensitive comparison
+            CompOp::Similar => {
+                json!({ "$regex": format!("^{}$", escape_regex(v)), "$options": "i" })
+            }
+            CompOp::NotSimilar => {
+                json!({ "$not": { "$regex": format!("^{}$", escape_regex(v)), "$options": "i" } })
+            }
+        };
+        Ok(Value::Object(Map::from_iter([(
+            f.db_name.to_string(),
+            cond,
+        )])))
+    }
+
+    fn visit_like(&mut self, f: &DbField, v: &str) -> Self::Output {
+        f.try_sql_like(v, self.0, None)?;
+        let cond = json!({ "$regex": glob_to_regex(v), "$options": "i" });
+        Ok(Value::Object(Map::from_iter([(
+            f.db_name.to_string(),
+            cond,
+        )])))
+    }
+
+    fn visit_denied(&mut self, f: &DbField) -> Self::Output {
+        Err(SuchError::Denied(f.db_name, f.permission))
+    }
+
+    fn visit_raw(&mut self, _: &str) -> Self::Output {
+        Err(ParseError("No raw SQL in MongoDB".to_string()))
+    }
+}
+
+/// Translates a glob-style pattern into a regular expression: `Duck*` becomes `^Duck`, `*uck*`
+/// becomes `uck`. Escaped wildcards stay literal.
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::with_capacity(glob.len() + 2);
+    let mut chars = glob.chars().peekable();
+    if chars.next_if_eq(&'*').is_none() {
+        regex.push('^');
+    }
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next_if(|n| matches!(n, '*' | '?')) {
+                Some(n) => regex.push_str(&escape_regex(&n.to_string())),
+                None => regex.push_str("\\\\"),
+            },
+            '*' if chars.peek().is_none() => return regex,
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&escape_regex(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+/// Escapes the characters with a meaning in a regular expression.
+fn escape_regex(text: &str) -> String {
+    let mut buf = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\^$.|?*+()[]{}".contains(c) {
+            buf.push('\\');
+        }
+        buf.push(c);
+    }
+    buf
+}
@@ -0,0 +1,68 @@
+use crate::db_field::DbField;
+
+/// Replaces field names written with spaces or hyphens by the alias of `fields` they stand for:
+/// `order date=2024` becomes `order_date=2024`. Only names followed by a comparator are
+/// replaced, quoted values stay untouched.
+pub(crate) fn normalize_field_names(query: &str, fields: &[DbField]) -> String {
+    let aliases = fields
+        .iter()
+        .flat_map(|f| f.alias.iter())
+        .filter(|a| a.contains('_'))
+        .collect::<Vec<_>>();
+    if aliases.is_empty() {
+        return query.to_string();
+    }
+    let mut buf = String::with_capacity(query.len());
+    let mut quote = None;
+    let mut in_word = false;
+    let mut pos = 0;
+    while let Some(c) = query[pos..].chars().next() {
+        if quote.is_none() && !in_word {
+            let rest = &query[pos..];
+            if let Some((alias, len)) = aliases
+                .iter()
+                .find_map(|alias| match_alias(rest, alias).map(|len| (alias, len)))
+            {
+                buf.push_str(alias);
+                pos += len;
+                continue;
+            }
+        }
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            _ => {}
+        }
+        in_word = c.is_alphanumeric() || matches!(c, '_' | '-' | '.');
+        buf.push(c);
+        pos += c.len_utf8();
+    }
+    buf
+}
+
+/// Length of the start of `rest` matching `alias`, where a `_` stands for any run of spaces,
+/// hyphens or underscores. The match has to be followed by a comparator.
+fn match_alias(rest: &str, alias: &str) -> Option<usize> {
+    let mut chars = rest.char_indices().peekable();
+    for a in alias.chars() {
+        if a == '_' {
+            let mut separated = false;
+            while chars
+                .next_if(|(_, c)| matches!(c, ' ' | '-' | '_'))
+                .is_some()
+            {
+                separated = true;
+            }
+            if !separated {
+                return None;
+            }
+        } else if chars.next_if(|(_, c)| c.eq_ignore_ascii_case(&a)).is_none() {
+            return None;
+        }
+    }
+    let len = chars.peek().map_or(rest.len(), |(i, _)| *i);
+    rest[len..]
+        .trim_start()
+        .starts_with(['=', '<', '>', '!', '~'])
+        .then_some(len)
+}
@@ -0,0 +1,51 @@
+use crate::error::SuchError;
+
+/// Maximum depth of macros using macros.
+pub(crate) const MAX_MACRO_DEPTH: usize = 8;
+
+/// Replaces every `@name` outside of quotes by the registered query-fragment in parentheses.
+/// Fragments may use other macros, up to `MAX_MACRO_DEPTH` levels.
+pub(crate) fn expand(
+    query: &str,
+    macros: &[(&'static str, &'static str)],
+    depth: usize,
+) -> Result<String, SuchError> {
+    if !query.contains('@') {
+        return Ok(query.to_string());
+    }
+    if depth >= MAX_MACRO_DEPTH {
+        return Err(SuchError::TooDeep);
+    }
+    let mut buf = String::with_capacity(query.len());
+    let mut quote = None;
+    let mut chars = query.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '@') => {
+                let mut end = pos + 1;
+                while let Some((i, n)) = chars.peek() {
+                    if n.is_ascii_alphanumeric() || *n == '_' || *n == '-' {
+                        end = i + n.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let name = &query[pos + 1..end];
+                let (_, fragment) = macros
+                    .iter()
+                    .find(|(m, _)| m.eq_ignore_ascii_case(name))
+                    .ok_or_else(|| SuchError::ParseError(format!("Unknown macro '@{name}'")))?;
+                buf.push('(');
+                buf.push_str(&expand(fragment, macros, depth + 1)?);
+                buf.push(')');
+                continue;
+            }
+            _ => {}
+        }
+        buf.push(c);
+    }
+    Ok(buf)
+}
@@ -2,56 +2,488 @@ use crate::comp_op::CompOp;
 use crate::db_field::DbField;
 use crate::error::SuchError;
 use crate::error::SuchError::ParseError;
-use std::fmt::{Display, Formatter};
-use std::ops::Deref;
+use crate::style::{Combinator, Style};
+use crate::suchbar::{DeniedInAnd, SuchOptions};
+use crate::visitor::SqlTermVisitor;
+use core::fmt::{Display, Formatter};
+use core::ops::Deref;
 use timewarp::Direction;
 
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum SQLTerm {
     AND(Vec<SQLTerm>),
     OR(Vec<SQLTerm>),
     NOT(Box<SQLTerm>),
     VALUE(DbField, CompOp, Direction, String),
     LIKE(DbField, String),
-    DENIED,
+    DENIED(DbField),
+    /// SQL given by the application, emitted as is, see `WhereClause::with_raw`.
+    RAW(String),
 }
 
 impl SQLTerm {
     pub fn to_sql(&self) -> Result<String, SuchError> {
-        use SQLTerm::{AND, DENIED, LIKE, NOT, OR, VALUE};
+        self.to_sql_with(&SuchOptions::new(), None)
+    }
+
+    /// Like `to_sql`, but appends the SQL to `buf`. On failure `buf` is left unchanged.
+    ///
+    /// # Errors
+    /// Like `to_sql` an empty or denied term causes a `SuchError`.
+    pub fn to_sql_into(&self, buf: &mut String) -> Result<(), SuchError> {
+        self.write_sql(buf, &SuchOptions::new(), None)
+    }
+
+    /// Like `to_sql`, every column prefixed by `table`, if given.
+    pub(crate) fn to_sql_with(
+        &self,
+        options: &SuchOptions,
+        table: Option<&str>,
+    ) -> Result<String, SuchError> {
+        let mut buf = String::new();
+        self.write_sql(&mut buf, options, table)?;
+        Ok(buf)
+    }
+
+    /// Like `to_sql_with`, but appends the SQL to `buf`. On failure `buf` is left unchanged.
+    pub(crate) fn write_sql(
+        &self,
+        buf: &mut String,
+        options: &SuchOptions,
+        table: Option<&str>,
+    ) -> Result<(), SuchError> {
+        use SQLTerm::{AND, DENIED, LIKE, NOT, OR, RAW, VALUE};
         match self {
-            OR(vec) => explode(vec, " OR "),
-            AND(vec) => explode(vec, " AND "),
+            OR(vec) => explode(buf, vec, Combinator::Or, options, table),
+            AND(vec) => explode(buf, vec, Combinator::And, options, table),
             NOT(val) => match val.deref() {
                 // NOT( NOT(val)) => val
-                NOT(inner) => inner.to_sql(),
-                _ => Ok(format!("NOT {}", val.to_sql()?)),
+                NOT(inner) => inner.write_sql(buf, options, table),
+                _ => {
+                    let start = buf.len();
+                    buf.push_str("NOT ");
+                    val.write_sql(buf, options, table)
+                        .inspect_err(|_| buf.truncate(start))
+                }
             },
-            VALUE(f, eq, d, v) => val_sql(f, *eq, v, *d),
-            LIKE(f, v) => f.try_sql_like(v),
-            DENIED => Err(SuchError::Denied),
+            VALUE(f, eq, d, v) => {
+                buf.push_str(&val_sql(f, *eq, v, *d, options, table)?);
+                Ok(())
+            }
+            LIKE(f, v) => {
+                buf.push_str(&f.try_sql_like(v, options, table)?);
+                Ok(())
+            }
+            DENIED(f) => Err(SuchError::Denied(f.db_name, f.permission)),
+            RAW(sql) => {
+                buf.push_str(sql);
+                Ok(())
+            }
+        }
+    }
+
+    /// Number of leaf terms, to estimate the length of the SQL.
+    pub(crate) fn leaf_count(&self) -> usize {
+        match self {
+            SQLTerm::AND(vec) | SQLTerm::OR(vec) => vec.iter().map(SQLTerm::leaf_count).sum(),
+            SQLTerm::NOT(inner) => inner.leaf_count(),
+            _ => 1,
+        }
+    }
+
+    /// Renders the term back into the search-language in the given `style`.
+    ///
+    /// # Errors
+    /// Like `to_sql` an empty or denied term causes a `SuchError`.
+    pub fn as_text(&self, style: Style) -> Result<String, SuchError> {
+        self.accept(&mut TextVisitor(style))
+    }
+}
+
+/// Renders a term back into the search-language, see `SQLTerm::as_text`.
+struct TextVisitor(Style);
+
+impl SqlTermVisitor for TextVisitor {
+    type Output = Result<String, SuchError>;
+
+    fn visit_and(&mut self, terms: &[SQLTerm]) -> Self::Output {
+        explode_text(terms, Combinator::And, self.0)
+    }
+
+    fn visit_or(&mut self, terms: &[SQLTerm]) -> Self::Output {
+        explode_text(terms, Combinator::Or, self.0)
+    }
+
+    fn visit_not(&mut self, term: &SQLTerm) -> Self::Output {
+        use crate::comp_op::CompOp::NotEqual;
+        use SQLTerm::{LIKE, NOT, VALUE};
+        match term {
+            NOT(inner) => inner.accept(self),
+            VALUE(f, eq, _, v) => Ok(f.as_text(self.0, !*eq, v)),
+            LIKE(f, v) => Ok(f.as_text(self.0, NotEqual, v)),
+            _ => Ok(self.0.invert(&term.accept(self)?)),
+        }
+    }
+
+    fn visit_value(&mut self, f: &DbField, eq: CompOp, _: Direction, v: &str) -> Self::Output {
+        Ok(f.as_text(self.0, eq, v))
+    }
+
+    fn visit_like(&mut self, f: &DbField, v: &str) -> Self::Output {
+        Ok(f.as_text(self.0, CompOp::Equal, v))
+    }
+
+    fn visit_denied(&mut self, f: &DbField) -> Self::Output {
+        Err(SuchError::Denied(f.db_name, f.permission))
+    }
+
+    fn visit_raw(&mut self, _: &str) -> Self::Output {
+        Err(ParseError(String::from("Raw SQL has no text")))
+    }
+}
+
+impl SQLTerm {
+    /// Splits off the terms on aggregate fields for the HAVING-clause: the terms combined by AND
+    /// at the top searching aggregates only. Returns the terms for the WHERE- and for the
+    /// HAVING-clause.
+    ///
+    /// # Errors
+    /// A term searching an aggregate and another field, like `order_count>5 OR name=Duck`.
+    pub(crate) fn split_aggregates(self) -> Result<(Self, Self), SuchError> {
+        let terms = match self {
+            SQLTerm::AND(vec) => vec,
+            term => vec![term],
+        };
+        let (mut whr, mut having) = (vec![], vec![]);
+        for term in terms {
+            match term.field_kinds() {
+                (true, true) => {
+                    return Err(ParseError(String::from(
+                        "Aggregate fields can only be combined by AND with other fields",
+                    )))
+                }
+                (true, false) => having.push(term),
+                _ => whr.push(term),
+            }
+        }
+        Ok((SQLTerm::AND(whr), SQLTerm::AND(having)))
+    }
+
+    /// Whether the term searches aggregate fields and whether it searches other fields.
+    fn field_kinds(&self) -> (bool, bool) {
+        use SQLTerm::{AND, DENIED, LIKE, NOT, OR, RAW, VALUE};
+        match self {
+            AND(vec) | OR(vec) => vec
+                .iter()
+                .map(SQLTerm::field_kinds)
+                .fold((false, false), |(a, o), (b, p)| (a || b, o || p)),
+            NOT(inner) => inner.field_kinds(),
+            VALUE(f, ..) | LIKE(f, _) | DENIED(f) => (f.aggregate, !f.aggregate),
+            RAW(_) => (false, true),
+        }
+    }
+
+    /// All reasons why this term can't become SQL. A failing alternative of an OR-group is fine
+    /// as long as another one works.
+    pub(crate) fn problems(&self, options: &SuchOptions) -> Vec<SuchError> {
+        use SQLTerm::{AND, DENIED, LIKE, NOT, OR, RAW, VALUE};
+        match self {
+            AND(vec) => vec.iter().flat_map(|t| t.problems(options)).collect(),
+            OR(vec) => {
+                let problems = vec.iter().map(|t| t.problems(options)).collect::<Vec<_>>();
+                if problems.iter().any(Vec::is_empty) {
+                    vec![]
+                } else {
+                    problems.into_iter().flatten().collect()
+                }
+            }
+            NOT(inner) => inner.problems(options),
+            VALUE(..) | LIKE(..) => self.to_sql_with(options, None).err().into_iter().collect(),
+            DENIED(f) => vec![SuchError::Denied(f.db_name, f.permission)],
+            RAW(_) => vec![],
+        }
+    }
+
+    /// A group without terms, as of an empty query.
+    pub(crate) fn is_empty(&self) -> bool {
+        matches!(self, SQLTerm::AND(vec) | SQLTerm::OR(vec) if vec.is_empty())
+    }
+
+    /// Any term is denied due to a missing permission.
+    pub(crate) fn has_denied(&self) -> bool {
+        use SQLTerm::{AND, DENIED, NOT, OR};
+        match self {
+            AND(vec) | OR(vec) => vec.iter().any(Self::has_denied),
+            NOT(inner) => inner.has_denied(),
+            DENIED(_) => true,
+            _ => false,
+        }
+    }
+
+    /// All leaves of the term are denied, none the user may search remains.
+    pub(crate) fn only_denied(&self) -> bool {
+        use SQLTerm::{AND, DENIED, NOT, OR};
+        match self {
+            AND(vec) | OR(vec) => vec.iter().all(Self::only_denied),
+            NOT(inner) => inner.only_denied(),
+            DENIED(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Drops the terms of a group made redundant by another term on the same text field: in an
+    /// AND `f=Eisen` makes `f=*ise*` redundant, in an OR it's the other way round. Only terms
+    /// of the same group are compared.
+    pub(crate) fn collapse_overlaps(self) -> Self {
+        use SQLTerm::{AND, NOT, OR};
+        match self {
+            AND(vec) => AND(collapse(vec, Combinator::And)),
+            OR(vec) => OR(collapse(vec, Combinator::Or)),
+            NOT(inner) => NOT(Box::new(inner.collapse_overlaps())),
+            term => term,
         }
     }
+
+    /// Joins the equality terms of an AND-group on the same field into an OR-group:
+    /// `art=100 art=200` searches either article, instead of none.
+    pub(crate) fn or_same_fields(self) -> Self {
+        use SQLTerm::{AND, NOT, OR, VALUE};
+        match self {
+            AND(vec) => {
+                let mut groups: Vec<(Option<String>, Vec<SQLTerm>)> = Vec::new();
+                for term in vec.into_iter().map(SQLTerm::or_same_fields) {
+                    let column = match single(&term) {
+                        VALUE(f, CompOp::Equal, _, _) => Some(f.column(None)),
+                        _ => None,
+                    };
+                    match groups
+                        .iter_mut()
+                        .find(|(c, _)| column.is_some() && *c == column)
+                    {
+                        Some((_, group)) => group.push(term),
+                        None => groups.push((column, vec![term])),
+                    }
+                }
+                AND(groups
+                    .into_iter()
+                    .map(|(_, mut group)| {
+                        if group.len() == 1 {
+                            group.remove(0)
+                        } else {
+                            OR(group)
+                        }
+                    })
+                    .collect())
+            }
+            OR(vec) => OR(vec.into_iter().map(SQLTerm::or_same_fields).collect()),
+            NOT(inner) => NOT(Box::new(inner.or_same_fields())),
+            term => term,
+        }
+    }
+}
+
+fn collapse(vec: Vec<SQLTerm>, combinator: Combinator) -> Vec<SQLTerm> {
+    use SQLTerm::{LIKE, VALUE};
+    let vec = vec
+        .into_iter()
+        .map(SQLTerm::collapse_overlaps)
+        .collect::<Vec<_>>();
+    let same_text_field =
+        |f: &DbField, g: &DbField| f.is_text() && g.is_text() && f.column(None) == g.column(None);
+    let exact = |v: &str| !v.contains(['?', '\\']);
+    let redundant = vec
+        .iter()
+        .map(|term| {
+            vec.iter()
+                .any(|other| match (combinator, single(term), single(other)) {
+                    (Combinator::And, LIKE(f, pattern), VALUE(g, CompOp::Equal, _, v))
+                    | (Combinator::Or, VALUE(f, CompOp::Equal, _, v), LIKE(g, pattern)) => {
+                        same_text_field(f, g) && exact(v) && glob_matches(pattern, v)
+                    }
+                    _ => false,
+                })
+        })
+        .collect::<Vec<_>>();
+    vec.into_iter()
+        .zip(redundant)
+        .filter_map(|(term, redundant)| (!redundant).then_some(term))
+        .collect()
 }
 
-fn val_sql(f: &DbField, eq: CompOp, v: &str, d: Direction) -> Result<String, SuchError> {
-    if v.contains('*') {
-        f.try_sql_like(v)
+/// Checks the emitted `sql` for balanced parentheses and closed quotes. A safety net against
+/// faulty emitters, computed fields and `CUSTOM` emitters included.
+pub(crate) fn check_balanced(sql: &str) -> Result<(), SuchError> {
+    let mut depth = Some(0_usize);
+    let mut quote = None;
+    for c in sql.chars() {
+        match (quote, c) {
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '(') => depth = depth.map(|d| d + 1),
+            (None, ')') => depth = depth.and_then(|d| d.checked_sub(1)),
+            _ => {}
+        }
+    }
+    if depth == Some(0) && quote.is_none() {
+        Ok(())
     } else {
-        f.try_sql_eq(eq, v, d)
+        Err(ParseError(format!("Unbalanced SQL: {sql}")))
+    }
+}
+
+/// The only term of nested single-term groups, like the fan-out of a term on a named field.
+fn single(term: &SQLTerm) -> &SQLTerm {
+    match term {
+        SQLTerm::AND(vec) | SQLTerm::OR(vec) if vec.len() == 1 => single(&vec[0]),
+        _ => term,
     }
 }
 
-fn explode(vec: &[SQLTerm], sep: &str) -> Result<String, SuchError> {
+/// `value` matches the glob-style `pattern`, where a backslash escapes `*` and `?`.
+fn glob_matches(pattern: &str, value: &str) -> bool {
+    // None is `*`, Some(None) is `?`
+    let mut glob = Vec::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        glob.push(match c {
+            '\\' => Some(Some(chars.next_if(|n| matches!(n, '*' | '?')).unwrap_or(c))),
+            '*' => None,
+            '?' => Some(None),
+            _ => Some(Some(c)),
+        });
+    }
+    let value = value.chars().collect::<Vec<_>>();
+    let (mut p, mut v) = (0, 0);
+    let mut star = None;
+    while v < value.len() {
+        match glob.get(p) {
+            Some(None) => {
+                star = Some((p, v));
+                p += 1;
+            }
+            Some(Some(c)) if c.is_none_or(|c| c == value[v]) => {
+                p += 1;
+                v += 1;
+            }
+            _ => match star {
+                Some((sp, sv)) => {
+                    star = Some((sp, sv + 1));
+                    p = sp + 1;
+                    v = sv + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[p..].iter().all(Option::is_none)
+}
+
+fn explode_text(
+    vec: &[SQLTerm],
+    combinator: Combinator,
+    style: Style,
+) -> Result<String, SuchError> {
     let v = vec
         .iter()
-        .filter_map(|op| op.to_sql().ok())
+        .filter_map(|op| op.as_text(style).ok())
         .collect::<Vec<String>>();
     match v.len() {
         0 => Err(ParseError("Empty SQLTerm!".to_string())),
         1 => Ok(v[0].clone()),
-        _ => Ok(format!("( {} )", v.join(sep))),
+        _ => Ok(style.group(&v, combinator)),
+    }
+}
+
+pub(crate) fn val_sql(
+    f: &DbField,
+    eq: CompOp,
+    v: &str,
+    d: Direction,
+    options: &SuchOptions,
+    table: Option<&str>,
+) -> Result<String, SuchError> {
+    if matches!(eq, CompOp::Match | CompOp::NotMatch) {
+        f.try_sql_regex(eq, v, options, table)
+    } else if matches!(eq, CompOp::Similar | CompOp::NotSimilar) {
+        f.try_sql_similar(eq, v, options, table)
+    } else if has_wildcard(v) {
+        f.try_sql_like(v, options, table)
+    } else {
+        f.try_sql_eq(eq, v, d, options, table)
+    }
+}
+
+/// `v` contains a `*` not escaped by a backslash.
+pub(crate) fn has_wildcard(v: &str) -> bool {
+    let mut escaped = false;
+    for c in v.chars() {
+        match c {
+            '*' if !escaped => return true,
+            '\\' => escaped = true,
+            _ => escaped = false,
+        }
+    }
+    false
+}
+
+/// Appends the `vec` joined by the `combinator` to `buf`, failing terms are left out. A group of
+/// denied terms only is denied itself. On failure `buf` is left unchanged.
+fn explode(
+    buf: &mut String,
+    vec: &[SQLTerm],
+    combinator: Combinator,
+    options: &SuchOptions,
+    table: Option<&str>,
+) -> Result<(), SuchError> {
+    let sep = match combinator {
+        Combinator::And => " AND ",
+        Combinator::Or => " OR ",
+    };
+    let start = buf.len();
+    buf.push_str("( ");
+    let mut written = 0;
+    // field and permission of the first denied term and the number of denied terms
+    let mut denied = None;
+    let mut denied_count = 0;
+    for op in vec {
+        let mark = buf.len();
+        if written > 0 {
+            buf.push_str(sep);
+        }
+        match op.write_sql(buf, options, table) {
+            Ok(()) => written += 1,
+            Err(err) => {
+                buf.truncate(mark);
+                if let SuchError::Denied(field, permission) = err {
+                    if combinator == Combinator::And && options.denied_in_and == DeniedInAnd::Fail {
+                        buf.truncate(start);
+                        return Err(SuchError::Denied(field, permission));
+                    }
+                    denied.get_or_insert((field, permission));
+                    denied_count += 1;
+                }
+            }
+        }
+    }
+    match written {
+        0 => {
+            buf.truncate(start);
+            match denied {
+                Some((field, permission)) if denied_count == vec.len() => {
+                    Err(SuchError::Denied(field, permission))
+                }
+                _ => Err(ParseError("Empty SQLTerm!".to_string())),
+            }
+        }
+        1 if !options.wrap_single_groups => {
+            buf.replace_range(start..start + 2, "");
+            Ok(())
+        }
+        _ => {
+            buf.push_str(" )");
+            Ok(())
+        }
     }
 }
 
@@ -62,7 +494,7 @@ impl Default for SQLTerm {
 }
 
 impl Display for SQLTerm {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", self.to_sql().unwrap_or_default())
     }
 }
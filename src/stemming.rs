@@ -0,0 +1,34 @@
+use rust_stemmers::{Algorithm, Stemmer};
+
+/// Language of the stemmer reducing field-free search terms to their stem, so `running` finds
+/// `run` too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stemming {
+    English,
+    German,
+}
+
+impl Stemming {
+    /// Stems every word of `value`. The stem is cut from the word as typed, keeping its case.
+    /// Words with a stem that isn't a prefix of them (`happy` → `happi`) are kept.
+    pub(crate) fn stem(self, value: &str) -> String {
+        let stemmer = Stemmer::create(match self {
+            Stemming::English => Algorithm::English,
+            Stemming::German => Algorithm::German,
+        });
+        value
+            .split(' ')
+            .map(|word| {
+                let lower = word.to_lowercase();
+                let stem = stemmer.stem(&lower);
+                if !stem.is_empty() && lower.starts_with(stem.as_ref()) && lower.len() == word.len()
+                {
+                    &word[..stem.len()]
+                } else {
+                    word
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
@@ -0,0 +1,114 @@
+use self::Style::{Compact, Html, Markdown, Pretty};
+
+/// How `SQLTerm::as_text` renders a query back into the search-language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Style {
+    /// Symbols without spaces: `art=12*&&(p>2||p<1)`
+    #[default]
+    Compact,
+    /// Keywords and spaces: `art = 12* AND ( p > 2 OR p < 1 )`
+    Pretty,
+    /// Like `Pretty`, every token is a `<span>` classed by its rule in the grammar.
+    Html,
+    /// Field names as code, combinators in bold: `` `art` = 12\* **&&** `p` > 2 ``
+    Markdown,
+}
+
+impl Style {
+    /// Wraps the `parts` of a group in parentheses.
+    pub(crate) fn group(self, parts: &[String], combinator: Combinator) -> String {
+        let sep = combinator.to_text(self);
+        match self {
+            Compact => format!("({})", parts.join(&sep)),
+            Pretty | Html | Markdown => format!("( {} )", parts.join(&sep)),
+        }
+    }
+
+    /// Negates an already rendered `text`.
+    pub(crate) fn invert(self, text: &str) -> String {
+        match self {
+            Compact => format!("!{text}"),
+            Pretty => format!("NOT {text}"),
+            Html => format!("{} {text}", span("invert", "NOT")),
+            Markdown => format!("**NOT** {text}"),
+        }
+    }
+}
+
+/// Connects the parts of a group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Combinator {
+    And,
+    Or,
+}
+
+impl Combinator {
+    pub(crate) fn to_text(self, style: Style) -> String {
+        match (self, style) {
+            (Combinator::And, Compact) => String::from("&&"),
+            (Combinator::Or, Compact) => String::from("||"),
+            (Combinator::And, Pretty) => String::from(" AND "),
+            (Combinator::Or, Pretty) => String::from(" OR "),
+            (Combinator::And, Markdown) => String::from(" **&&** "),
+            (Combinator::Or, Markdown) => String::from(" **||** "),
+            (_, Html) => format!(" {} ", self.to_html()),
+        }
+    }
+
+    fn to_html(self) -> String {
+        match self {
+            Combinator::And => span("and", "AND"),
+            Combinator::Or => span("or", "OR"),
+        }
+    }
+}
+
+/// Wraps `text` into a span of the given `class`. `text` gets escaped.
+pub(crate) fn span(class: &str, text: &str) -> String {
+    format!("<span class=\"{class}\">{}</span>", escape_html(text))
+}
+
+/// Escapes the characters with a meaning in Markdown.
+pub(crate) fn escape_markdown(text: &str) -> String {
+    let mut buf = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            buf.push('\\');
+        }
+        buf.push(c);
+    }
+    buf
+}
+
+fn escape_html(text: &str) -> String {
+    let mut buf = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => buf.push_str("&lt;"),
+            '>' => buf.push_str("&gt;"),
+            '&' => buf.push_str("&amp;"),
+            '"' => buf.push_str("&quot;"),
+            '\'' => buf.push_str("&#39;"),
+            _ => buf.push(c),
+        }
+    }
+    buf
+}
+
+/// Quotes `value` if it can't be written as a plain word of the search-language.
+pub(crate) fn quote_value(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value.chars().all(|c| {
+            c.is_ascii_alphanumeric() || matches!(c, '.' | ',' | '/' | ':' | '-' | '*' | '?')
+        });
+    if plain {
+        value.to_string()
+    } else if value.contains('"') {
+        format!("'{}'", value.replace('\'', "''"))
+    } else {
+        format!("\"{value}\"")
+    }
+}
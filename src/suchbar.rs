@@ -1,12 +1,24 @@
+use crate::alias_index::alias_index;
 use crate::comp_op::CompOp;
 use crate::comp_op::CompOp::{Equal, NotEqual};
 use crate::db_field::{DbField, SortField};
 use crate::error::SuchError;
-use crate::sql_term::SQLTerm;
+use crate::explanation::{FieldInfo, QueryExplanation};
+use crate::leaf_term::{LeafTerm, Leaves};
+#[cfg(feature = "mongo")]
+use crate::mongo::MongoVisitor;
+use crate::normalize::normalize_field_names;
+use crate::query_macro;
 use crate::sql_term::SQLTerm::{AND, DENIED, LIKE, NOT, OR, VALUE};
+use crate::sql_term::{check_balanced, SQLTerm};
+#[cfg(feature = "stemming")]
+use crate::stemming::Stemming;
+use crate::style::Style;
+use crate::visitor::SqlTermVisitor;
 use permeable::Permeable;
 use pest::iterators::Pair;
 use pest::Parser;
+use std::borrow::Cow;
 use std::fmt::{Display, Write};
 use std::ops::Not;
 use std::str::FromStr;
@@ -14,25 +26,420 @@ use timewarp::Direction;
 
 type SuchResult = Result<SQLTerm, SuchError>;
 
+/// Emits a debug event with rule and text of the parsed `pair`, with the feature `tracing` only.
+macro_rules! trace_pair {
+    ($parser:literal, $pair:expr) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(rule = ?$pair.as_rule(), text = $pair.as_str(), $parser);
+    };
+}
+
+/// Replaces the `quote` escaped by a backslash or by doubling it with the `quote` itself.
+fn unescape_quotes(interior: &str, quote: char) -> String {
+    let mut value = String::with_capacity(interior.len());
+    let mut chars = interior.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' || c == quote {
+            if let Some(q) = chars.next_if_eq(&quote) {
+                value.push(q);
+                continue;
+            }
+        }
+        value.push(c);
+    }
+    value
+}
+
+/// A rule the grammar doesn't allow at this place, a bug of the parser.
+fn unexpected(pair: &Pair<Rule>) -> SuchError {
+    SuchError::ParseError(format!(
+        "Unexpected {:?} '{}'",
+        pair.as_rule(),
+        pair.as_str()
+    ))
+}
+
+/// Negates a group, `NOT (NOT a)` collapses to `a`.
+fn negate(term: SQLTerm) -> SQLTerm {
+    match term {
+        AND(mut vec) | OR(mut vec) if vec.len() == 1 => negate(vec.remove(0)),
+        NOT(inner) => *inner,
+        term => NOT(Box::new(term)),
+    }
+}
+
 #[derive(Parser, Debug)]
 #[grammar = "suchbar.pest"]
 pub struct Suchbar {
     db_fields: &'static [DbField],
     options: SuchOptions,
+    macros: &'static [(&'static str, &'static str)],
+    groups: &'static [(&'static str, &'static [&'static str])],
+}
+
+/// What happens to an AND-group with a term on a field the user isn't allowed to search.
+///
+/// Dropping the term widens the result: `price<10 AND age>18` without permission for `age`
+/// finds all cheap entries, including the ones the condition on `age` should hide. An OR-group
+/// always drops the denied term, that only narrows the result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeniedInAnd {
+    /// The whole group fails with `SuchError::Denied`. Within an OR it's dropped, otherwise
+    /// the query fails. The failed query finds nothing, even under `EmptyPolicy::MatchAll`: the
+    /// group has terms the user may search, so not all of them are denied.
+    #[default]
+    Fail,
+    /// The denied term is dropped, the other terms of the group remain.
+    Drop,
+}
+
+/// What the WHERE-clause of a query is, when all of its terms are denied.
+///
+/// An empty WHERE-clause matches all rows: the user would see exactly what the permissions
+/// should hide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmptyPolicy {
+    /// The WHERE-clause is `FALSE`, nothing is found.
+    #[default]
+    MatchNothing,
+    /// The WHERE-clause is empty, everything is found. Raw filters and the keyset still apply.
+    MatchAll,
+    /// `Suchbar::exec` fails with `SuchError::Denied`.
+    Error,
+}
+
+/// The character quoting string values in the SQL. Inside a value it's escaped by doubling,
+/// for `Double` and `Backtick` the backslash as well.
+///
+/// Paths of `JSONB` fields, full-text configurations and time zones are quoted alike. Values of
+/// `CUSTOM` fields are quoted by their emitter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ValueQuote {
+    /// `'value'`, standard SQL.
+    #[default]
+    Single,
+    /// `"value"`, like MySQL without `ANSI_QUOTES`.
+    Double,
+    /// `` `value` ``
+    Backtick,
+}
+
+impl ValueQuote {
+    pub(crate) const fn char(self) -> char {
+        match self {
+            ValueQuote::Single => '\'',
+            ValueQuote::Double => '"',
+            ValueQuote::Backtick => '`',
+        }
+    }
 }
 
-#[derive(Default, Debug)]
+/// Fine-tuning of a `Suchbar`.
+///
+/// Defaults: no LIKE in numeric fields, no limit on the query length and at most
+/// `DEFAULT_MAX_DEPTH` nested groups. For queries coming from the internet a
+/// `max_query_len` of a few KiB is a safe choice, it also bounds the recursion of the parser.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct SuchOptions {
     like_in_numerics: bool,
+    max_query_len: Option<usize>,
+    max_depth: usize,
+    pub(crate) case_insensitive_sort: bool,
+    prefix_only: bool,
+    ambiguous_alias_error: bool,
+    reject_plus_in_numerics: bool,
+    pub(crate) unaccent: bool,
+    wildcard_field_names: bool,
+    pub(crate) wrap_single_groups: bool,
+    pub(crate) enum_by_index: bool,
+    pub(crate) denied_in_and: DeniedInAnd,
+    normalize_field_names: bool,
+    empty_on_all_denied: EmptyPolicy,
+    pub(crate) round_to_scale: bool,
+    error_on_empty: bool,
+    pub(crate) value_quote: ValueQuote,
+    default_fields: &'static [&'static str],
+    collapse_overlaps: bool,
+    inclusive_upper: bool,
+    same_field_or: bool,
+    strict_fields: bool,
+    decimal_sep: char,
+    thousands_sep: Option<char>,
+    pub(crate) table_prefix: Option<&'static str>,
+    default_sort: &'static [(&'static str, bool)],
+    #[cfg(feature = "stemming")]
+    stemming: Option<Stemming>,
 }
 
 impl SuchOptions {
+    /// Default for the maximum nesting of `( )` groups.
+    pub const DEFAULT_MAX_DEPTH: usize = 32;
+
     #[must_use]
     pub const fn new() -> Self {
         Self {
             like_in_numerics: false,
+            max_query_len: None,
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+            case_insensitive_sort: false,
+            prefix_only: false,
+            ambiguous_alias_error: false,
+            reject_plus_in_numerics: false,
+            unaccent: false,
+            wildcard_field_names: false,
+            wrap_single_groups: false,
+            enum_by_index: false,
+            denied_in_and: DeniedInAnd::Fail,
+            normalize_field_names: false,
+            empty_on_all_denied: EmptyPolicy::MatchNothing,
+            round_to_scale: false,
+            error_on_empty: false,
+            value_quote: ValueQuote::Single,
+            default_fields: &[],
+            collapse_overlaps: false,
+            inclusive_upper: false,
+            same_field_or: false,
+            strict_fields: false,
+            decimal_sep: ',',
+            thousands_sep: None,
+            table_prefix: None,
+            default_sort: &[],
+            #[cfg(feature = "stemming")]
+            stemming: None,
         }
     }
+
+    /// Field-free terms are searched with LIKE in numeric fields too.
+    #[must_use]
+    pub const fn like_in_numerics(mut self, like_in_numerics: bool) -> Self {
+        self.like_in_numerics = like_in_numerics;
+        self
+    }
+
+    /// Queries longer than `max_query_len` bytes are rejected with `SuchError::TooLong`
+    /// before they reach the parser.
+    #[must_use]
+    pub const fn max_query_len(mut self, max_query_len: usize) -> Self {
+        self.max_query_len = Some(max_query_len);
+        self
+    }
+
+    /// Queries with more than `max_depth` nested groups are rejected with `SuchError::TooDeep`.
+    #[must_use]
+    pub const fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Text fields are sorted by `LOWER(field)`, so "apple" and "Apple" are next to each other.
+    #[must_use]
+    pub const fn case_insensitive_sort(mut self, case_insensitive_sort: bool) -> Self {
+        self.case_insensitive_sort = case_insensitive_sort;
+        self
+    }
+
+    /// Field-free terms are searched as prefix (`value%`) instead of substring (`%value%`),
+    /// so an index on the column can be used. An explicit `*value*` is still a substring search.
+    #[must_use]
+    pub const fn prefix_only(mut self, prefix_only: bool) -> Self {
+        self.prefix_only = prefix_only;
+        self
+    }
+
+    /// An alias used by more than one `DbField` fails with `SuchError::AmbiguousAlias`.
+    /// Otherwise the first field in declaration order wins.
+    #[must_use]
+    pub const fn ambiguous_alias_error(mut self, ambiguous_alias_error: bool) -> Self {
+        self.ambiguous_alias_error = ambiguous_alias_error;
+        self
+    }
+
+    /// Numeric values with a leading `+` (e.g. a phone number searched in a numeric field) are
+    /// refused instead of being searched without the sign. Text fields keep the `+` anyway.
+    #[must_use]
+    pub const fn reject_plus_in_numerics(mut self, reject_plus_in_numerics: bool) -> Self {
+        self.reject_plus_in_numerics = reject_plus_in_numerics;
+        self
+    }
+
+    /// LIKE-searches in text fields ignore case and accents: `cafe` finds `Café`.
+    /// Emits `unaccent(field) ILIKE unaccent('..')`, which needs PostgreSQL with the
+    /// `unaccent` extension installed (`CREATE EXTENSION unaccent;`).
+    #[must_use]
+    pub const fn unaccent(mut self, unaccent: bool) -> Self {
+        self.unaccent = unaccent;
+        self
+    }
+
+    /// A field name ending with `*` searches all fields with an alias starting with it:
+    /// `art*=5` searches `artikelnummer` and `artikeltext`. Without this option such a field
+    /// name is an error.
+    #[must_use]
+    pub const fn wildcard_field_names(mut self, wildcard_field_names: bool) -> Self {
+        self.wildcard_field_names = wildcard_field_names;
+        self
+    }
+
+    /// Every AND/OR group gets its parentheses, even with a single term: `art=5` emits
+    /// `( ( artikelnummer='5' ) )` instead of `artikelnummer='5'`. Gives the same structure for
+    /// any query, for processing the SQL further.
+    #[must_use]
+    pub const fn wrap_single_groups(mut self, wrap_single_groups: bool) -> Self {
+        self.wrap_single_groups = wrap_single_groups;
+        self
+    }
+
+    /// A number searched in an `ENUM` field is the position of the value, starting at 1:
+    /// `status=2` searches the second value. Positions out of range are refused.
+    #[must_use]
+    pub const fn enum_by_index(mut self, enum_by_index: bool) -> Self {
+        self.enum_by_index = enum_by_index;
+        self
+    }
+
+    /// How a denied term in an AND-group is handled, see `DeniedInAnd`.
+    #[must_use]
+    pub const fn denied_in_and(mut self, denied_in_and: DeniedInAnd) -> Self {
+        self.denied_in_and = denied_in_and;
+        self
+    }
+
+    /// Field names may be written with spaces or hyphens instead of the underscores of an
+    /// alias: `order date=2024` searches the alias `order_date`.
+    #[must_use]
+    pub const fn normalize_field_names(mut self, normalize_field_names: bool) -> Self {
+        self.normalize_field_names = normalize_field_names;
+        self
+    }
+
+    /// What to do with a query whose terms are all denied, see `EmptyPolicy`.
+    #[must_use]
+    pub const fn empty_on_all_denied(mut self, empty_on_all_denied: EmptyPolicy) -> Self {
+        self.empty_on_all_denied = empty_on_all_denied;
+        self
+    }
+
+    /// Values compared with a `NUMERIC` field are rounded to the scale of the field, half away
+    /// from zero like PostgreSQL rounds a `NUMERIC`: `price<10.999` on `NUMERIC(12, 2)` emits
+    /// `price<11.00`. LIKE-searches keep the value as typed.
+    #[must_use]
+    pub const fn round_to_scale(mut self, round_to_scale: bool) -> Self {
+        self.round_to_scale = round_to_scale;
+        self
+    }
+
+    /// An empty query, or one of whitespace only, fails with `SuchError::EmptyQuery` instead of
+    /// giving an empty clause.
+    #[must_use]
+    pub const fn error_on_empty(mut self, error_on_empty: bool) -> Self {
+        self.error_on_empty = error_on_empty;
+        self
+    }
+
+    /// The character quoting string values, see `ValueQuote`.
+    #[must_use]
+    pub const fn value_quote(mut self, value_quote: ValueQuote) -> Self {
+        self.value_quote = value_quote;
+        self
+    }
+
+    /// Field-free terms search only the fields with one of these aliases, like a search box
+    /// over name and description. Empty, all fields not `DbField::hidden` are searched. An alias
+    /// of no field makes field-free terms fail with `SuchError::UnknownField`.
+    #[must_use]
+    pub const fn default_fields(mut self, default_fields: &'static [&'static str]) -> Self {
+        self.default_fields = default_fields;
+        self
+    }
+
+    /// Drops a term of a group made redundant by another one on the same text field:
+    /// `ptext=Eisen ptext=*ise*` searches `positionstext='Eisen'` only, with OR it's the LIKE.
+    #[must_use]
+    pub const fn collapse_overlaps(mut self, collapse_overlaps: bool) -> Self {
+        self.collapse_overlaps = collapse_overlaps;
+        self
+    }
+
+    /// The upper bound of a range in a numeric field is included: `age=10-19` searches
+    /// `age>=10 AND age<=19` instead of `age<19`. Date ranges keep the exclusive bound, there
+    /// `changed=Feb-Dez` already ends before the first day after December.
+    #[must_use]
+    pub const fn inclusive_upper(mut self, inclusive_upper: bool) -> Self {
+        self.inclusive_upper = inclusive_upper;
+        self
+    }
+
+    /// Equality terms on the same field within an AND-group are joined by OR: `art=100 art=200`
+    /// searches `( artikelnummer='100' OR artikelnummer='200' )`, which otherwise finds nothing.
+    #[must_use]
+    pub const fn same_field_or(mut self, same_field_or: bool) -> Self {
+        self.same_field_or = same_field_or;
+        self
+    }
+
+    /// An unknown field name fails with `SuchError::UnknownField` instead of searching the
+    /// default fields: `xyz=5` isn't searched everywhere. Field-free terms aren't affected.
+    #[must_use]
+    pub const fn strict_fields(mut self, strict_fields: bool) -> Self {
+        self.strict_fields = strict_fields;
+        self
+    }
+
+    /// The decimal separator of numbers, `,` by default. A `.` is taken as decimal separator too,
+    /// unless it's the `thousands_sep`.
+    #[must_use]
+    pub const fn decimal_sep(mut self, decimal_sep: char) -> Self {
+        self.decimal_sep = decimal_sep;
+        self
+    }
+
+    /// The thousands separator of numbers, removed before checking the value: with `.` the value
+    /// `1.234,56` is `1234.56`. There's none by default.
+    #[must_use]
+    pub const fn thousands_sep(mut self, thousands_sep: char) -> Self {
+        self.thousands_sep = Some(thousands_sep);
+        self
+    }
+
+    /// Every column is qualified by `table_prefix`, like `offers.artikelnummer`, in the WHERE
+    /// clause and the ORDER BY, for joins. `WhereClause::to_sql_aliased` overrides it.
+    #[must_use]
+    pub const fn table_prefix(mut self, table_prefix: &'static str) -> Self {
+        self.table_prefix = Some(table_prefix);
+        self
+    }
+
+    /// Sorts by these aliases, each descending if flagged, when the query gives no sort: with
+    /// `&[("changed", true)]` a list shows the latest first. Any sort in the query replaces it.
+    /// Unknown and ambiguous aliases are left out.
+    #[must_use]
+    pub const fn default_sort(mut self, default_sort: &'static [(&'static str, bool)]) -> Self {
+        self.default_sort = default_sort;
+        self
+    }
+
+    /// The number `val` with the separators as SQL expects them.
+    pub(crate) fn number(&self, val: &str) -> String {
+        val.chars()
+            .filter(|c| Some(*c) != self.thousands_sep)
+            .map(|c| if c == self.decimal_sep { '.' } else { c })
+            .collect()
+    }
+
+    /// Field-free terms are searched by their stem in text fields: `running` searches
+    /// `%run%`, finding `run` and `runner` too.
+    #[cfg(feature = "stemming")]
+    #[must_use]
+    pub const fn stemming(mut self, stemming: Stemming) -> Self {
+        self.stemming = Some(stemming);
+        self
+    }
+}
+
+impl Default for SuchOptions {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Suchbar {
@@ -41,21 +448,124 @@ impl Suchbar {
         Self {
             db_fields,
             options: SuchOptions::new(),
+            macros: &[],
+            groups: &[],
         }
     }
 
-    /// Returns a explanation which fields are usable for the search.
-    /// Shows only fields the user has `permission` to see.
+    /// Replaces the `SuchOptions` of this `Suchbar`.
+    #[must_use]
+    pub const fn with_options(mut self, options: SuchOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Registers named query-fragments, usable in a query as `@name`.
+    ///
+    /// ```rust
+    /// use permeable::AllowAllPermission;
+    /// use suchbar::*;
+    /// use suchbar::DbType::{NUMERIC, TEXT};
+    ///
+    /// const SUCHBAR: Suchbar = Suchbar::new(&[
+    ///   DbField::new("name", TEXT, "STD", &["name", "n"]),
+    ///   DbField::new("price", NUMERIC(8, 2), "STD", &["price", "p"]),
+    /// ])
+    /// .with_macros(&[("cheap", "price<10")]);
+    ///
+    /// let exec = SUCHBAR.exec(&AllowAllPermission(), "@cheap n=Duck*").unwrap();
+    /// assert_eq!("( price<10 AND name LIKE 'Duck%' )", exec.where_clause().unwrap());
+    /// ```
+    #[must_use]
+    pub const fn with_macros(mut self, macros: &'static [(&'static str, &'static str)]) -> Self {
+        self.macros = macros;
+        self
+    }
+
+    /// Registers names for groups of fields, given by their aliases. A term on a group searches
+    /// all its fields joined by OR, `!=` finds rows where none of them matches. The user needs
+    /// the permissions of all fields of the group.
+    ///
+    /// ```rust
+    /// use permeable::AllowAllPermission;
+    /// use suchbar::*;
+    /// use suchbar::DbType::{TEXT, VARCHAR};
+    ///
+    /// const SUCHBAR: Suchbar = Suchbar::new(&[
+    ///   DbField::new("street", TEXT, "STD", &["street"]),
+    ///   DbField::new("city", TEXT, "STD", &["city"]),
+    ///   DbField::new("zip", VARCHAR(10), "STD", &["zip"]),
+    /// ])
+    /// .with_groups(&[("address", &["street", "city", "zip"])]);
+    ///
+    /// let exec = SUCHBAR.exec(&AllowAllPermission(), "address=Berlin").unwrap();
+    /// assert_eq!(
+    ///     "( street='Berlin' OR city='Berlin' OR zip='Berlin' )",
+    ///     exec.where_clause().unwrap()
+    /// );
+    /// ```
+    #[must_use]
+    pub const fn with_groups(
+        mut self,
+        groups: &'static [(&'static str, &'static [&'static str])],
+    ) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// Returns a explanation which fields are usable for the search, in the order field-free
+    /// terms search them. Shows only fields the user has `permission` to see. An `ENUM` field
+    /// lists the values it accepts: `[status] ENUM (active, closed)`.
     pub fn explanation(&self, permission: &impl Permeable) -> String {
         let mut buf = String::new();
-        for field in self.db_fields {
-            if permission.has_perm(field.permission).is_ok() {
-                writeln!(&mut buf, "{} {}", field.aliases(), field.db_type()).expect("");
+        for info in self.fields_for(permission) {
+            writeln!(&mut buf, "{}", info.line()).expect("");
+        }
+        buf
+    }
+
+    /// Like `explanation`, but lists the fields the user lacks the `permission` for too, marked
+    /// by the permission they require: `[age] NUMBER (requires ACCESS_PRIVATE)`. Meant for admins
+    /// and support tooling, as it reveals the restricted fields.
+    pub fn explanation_full(&self, permission: &impl Permeable) -> String {
+        let mut buf = String::new();
+        for field in Self::by_priority(self.db_fields.iter()) {
+            buf.push_str(&FieldInfo::from_field(field).line());
+            if permission.has_perm(field.permission).is_err() {
+                write!(&mut buf, " (requires {})", field.permission).expect("");
             }
+            buf.push('\n');
         }
         buf
     }
 
+    /// The fields usable for the search as data, in the same order as `explanation`, to render
+    /// a help however needed. Contains only fields the user has `permission` to see.
+    ///
+    /// # Example
+    /// ```rust
+    /// use permeable::AllowAllPermission;
+    /// use suchbar::*;
+    /// use suchbar::DbType::{NUMERIC, TEXT};
+    ///
+    /// const SUCHBAR: Suchbar = Suchbar::new(&[
+    ///   DbField::new("name", TEXT, "STD", &["name", "n"]),
+    ///   DbField::new("price", NUMERIC(8, 2), "STD", &["price", "p"]),
+    /// ]);
+    ///
+    /// let fields = SUCHBAR.fields_for(&AllowAllPermission());
+    /// assert_eq!(vec!["price", "p"], fields[1].aliases);
+    /// assert_eq!("NUMBER", fields[1].db_type);
+    /// assert_eq!("price>10,5", fields[1].example);
+    /// ```
+    pub fn fields_for(&self, permission: &impl Permeable) -> Vec<FieldInfo> {
+        Self::by_priority(self.db_fields.iter())
+            .into_iter()
+            .filter(|field| permission.has_perm(field.permission).is_ok())
+            .map(FieldInfo::from_field)
+            .collect()
+    }
+
     /// Creates a `WhereClause` from the given `query` depending on th user's `permission`.
     ///
     /// # Errors
@@ -64,68 +574,316 @@ impl Suchbar {
         &self,
         permission: &impl Permeable,
         query: impl Into<String>,
+    ) -> Result<WhereClause, SuchError> {
+        let query = self.prepare(query.into())?;
+        self.exec_prepared(permission, &query)
+    }
+
+    /// Checks the `query` without building SQL: it has to parse, every field has to be known
+    /// and searchable with the user's `permission` and every value has to fit its field.
+    ///
+    /// # Example
+    /// ```rust
+    /// use permeable::AllowAllPermission;
+    /// use suchbar::*;
+    /// use suchbar::DbType::{NUMERIC, TEXT};
+    ///
+    /// const SUCHBAR: Suchbar = Suchbar::new(&[
+    ///   DbField::new("name", TEXT, "STD", &["name", "n"]),
+    ///   DbField::new("price", NUMERIC(8, 2), "STD", &["price", "p"]),
+    /// ]);
+    ///
+    /// assert!(SUCHBAR.validate(&AllowAllPermission(), "n=Duck* p<10").is_ok());
+    /// let errors = SUCHBAR.validate(&AllowAllPermission(), "colour=red p<ten").unwrap_err();
+    /// assert_eq!(2, errors.len());
+    /// ```
+    ///
+    /// # Errors
+    /// All problems found in the `query`.
+    pub fn validate(
+        &self,
+        permission: &impl Permeable,
+        query: impl Into<String>,
+    ) -> Result<(), Vec<SuchError>> {
+        let query = self.prepare(query.into()).map_err(|e| vec![e])?;
+        let pairs = Self::parse(Rule::query, &query).map_err(|e| vec![e.into()])?;
+        let mut errors = Vec::new();
+        for name in pairs.flatten().filter(|p| p.as_rule() == Rule::field_name) {
+            let name = name.as_str();
+            match self.choose_field(name) {
+                Ok(None) if !name.ends_with('*') && self.group(name).is_none() => {
+                    errors.push(SuchError::UnknownField(name.to_string()));
+                }
+                Err(e) => errors.push(e),
+                _ => {}
+            }
+        }
+        match self.exec_prepared(permission, &query) {
+            Ok(wc) => errors.extend(wc.whole_term().problems(&wc.options)),
+            // with `strict_fields` already found above
+            Err(SuchError::UnknownField(_)) => {}
+            Err(e) => errors.push(e),
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Checks the length of the `query` and expands macros and field names.
+    fn prepare(&self, query: String) -> Result<String, SuchError> {
+        if self.options.error_on_empty && query.trim().is_empty() {
+            return Err(SuchError::EmptyQuery);
+        }
+        if self
+            .options
+            .max_query_len
+            .is_some_and(|max| query.len() > max)
+        {
+            return Err(SuchError::TooLong);
+        }
+        let mut query = query_macro::expand(&query, self.macros, 0)?;
+        if self.options.normalize_field_names {
+            query = normalize_field_names(&query, self.db_fields);
+        }
+        if self
+            .options
+            .max_query_len
+            .is_some_and(|max| query.len() > max)
+        {
+            return Err(SuchError::TooLong);
+        }
+        Ok(query)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(self, permission))
+    )]
+    fn exec_prepared(
+        &self,
+        permission: &impl Permeable,
+        query: &str,
     ) -> Result<WhereClause, SuchError> {
         let mut sql_term = AND(vec![]);
         let mut sort_field = vec![];
-        let query = query.into();
-        let qu = Self::parse(Rule::query, &query)?;
+        let mut segments = 0;
+        let qu = Self::parse(Rule::query, query)?;
         for expr in qu {
             match expr.as_rule() {
-                Rule::expr => sql_term = self.parse_expr(permission, expr)?,
-                Rule::sort => sort_field = self.parse_sort(expr),
+                Rule::expr => sql_term = self.parse_expr(permission, expr, 0)?,
+                Rule::sort => {
+                    self.parse_sort(expr, &mut sort_field, segments > 0)?;
+                    segments += 1;
+                }
                 _ => {} //ignore EOI and rest
             }
         }
-        Ok(WhereClause {
+        if sort_field.is_empty() {
+            for (alias, desc) in self.options.default_sort {
+                // an ambiguous alias of the configuration mustn't fail every query
+                if let Ok(Some(field)) = self.choose_field(alias) {
+                    let column = field.column(None);
+                    if !sort_field.iter().any(|sf| sf.field.column(None) == column) {
+                        sort_field.push(SortField { desc: *desc, field });
+                    }
+                }
+            }
+        }
+        if self.options.same_field_or {
+            sql_term = sql_term.or_same_fields();
+        }
+        if self.options.collapse_overlaps {
+            sql_term = sql_term.collapse_overlaps();
+        }
+        let (sql_term, having_term) = sql_term.split_aggregates()?;
+        let wc = WhereClause {
             sql_term,
+            having_term,
+            group_by: vec![],
             sort_field,
-        })
+            options: self.options,
+            keyset: None,
+            raw: vec![],
+        };
+        if self.options.empty_on_all_denied == EmptyPolicy::Error {
+            if let Err(denied @ SuchError::Denied(..)) =
+                wc.whole_term().to_sql_with(&wc.options, None)
+            {
+                return Err(denied);
+            }
+        }
+        Ok(wc)
+    }
+
+    /// Explains which fields, comparisons and values were understood from the `query`
+    /// and which terms were denied due to missing `permission`.
+    ///
+    /// # Errors
+    /// Failures in `query` can cause a `SuchError`.
+    pub fn explain_query(
+        &self,
+        permission: &impl Permeable,
+        query: impl Into<String>,
+    ) -> Result<QueryExplanation, SuchError> {
+        let where_clause = self.exec(permission, query)?;
+        Ok(QueryExplanation::from_term(&where_clause.whole_term()))
     }
 
-    fn choose_field(&self, needle: &str) -> Option<DbField> {
+    fn choose_field(&self, needle: &str) -> Result<Option<DbField>, SuchError> {
+        if let Some((name, path)) = needle.split_once('.') {
+            return self
+                .choose_field(name)?
+                .map(|field| field.with_path(path))
+                .transpose();
+        }
         let needle = needle.to_ascii_lowercase();
-        self.db_fields
-            .iter()
-            .find(|sf| sf.alias.iter().any(|s| *s == needle))
-            .cloned()
+        let index = alias_index(self.db_fields);
+        let mut found = index
+            .get(needle.as_str())
+            .into_iter()
+            .flatten()
+            .map(|pos| &self.db_fields[*pos]);
+        let first = found.next();
+        if self.options.ambiguous_alias_error {
+            if let Some(first) = first {
+                let others = found.map(|sf| sf.db_name).collect::<Vec<_>>();
+                if !others.is_empty() {
+                    let mut candidates = vec![first.db_name];
+                    candidates.extend(others);
+                    return Err(SuchError::AmbiguousAlias(needle, candidates));
+                }
+            }
+        }
+        Ok(first.cloned())
     }
 
-    fn choose_field_vec(&self, needle: &str) -> Vec<DbField> {
-        if let Some(f) = self.choose_field(needle) {
-            vec![f]
+    fn choose_field_vec(&self, needle: &str) -> Result<Vec<DbField>, SuchError> {
+        if let Some(prefix) = needle.strip_suffix('*') {
+            if !self.options.wildcard_field_names {
+                return Err(SuchError::ParseError(format!("Unknown field '{needle}'")));
+            }
+            let prefix = prefix.to_ascii_lowercase();
+            let found = self
+                .db_fields
+                .iter()
+                .filter(|sf| sf.alias.iter().any(|s| s.starts_with(&prefix)))
+                .cloned()
+                .collect::<Vec<_>>();
+            if !found.is_empty() {
+                return Ok(found);
+            }
+        }
+        if let Some(f) = self.choose_field(needle)? {
+            Ok(vec![f])
+        } else if let Some(aliases) = self.group(needle) {
+            aliases
+                .iter()
+                .filter_map(|alias| self.choose_field(alias).transpose())
+                .collect()
+        } else if self.options.strict_fields && !needle.is_empty() {
+            Err(SuchError::UnknownField(needle.to_string()))
         } else {
-            self.db_fields.to_vec()
+            let default_fields = self.options.default_fields;
+            // a misspelled alias would drop field-free terms silently
+            if let Some(unknown) = default_fields
+                .iter()
+                .find(|a| !self.db_fields.iter().any(|f| f.alias.contains(a)))
+            {
+                return Err(SuchError::UnknownField((*unknown).to_string()));
+            }
+            Ok(Self::by_priority(self.db_fields.iter().filter(|f| {
+                if default_fields.is_empty() {
+                    f.searchable_by_default
+                } else {
+                    f.alias.iter().any(|a| default_fields.contains(a))
+                }
+            }))
+            .into_iter()
+            .cloned()
+            .collect())
         }
     }
 
+    /// The aliases of the fields of the group named `needle`.
+    fn group(&self, needle: &str) -> Option<&'static [&'static str]> {
+        self.groups
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(needle))
+            .map(|(_, aliases)| *aliases)
+    }
+
+    /// A group of fields is denied as a whole, if any of its `fields` is denied.
+    fn group_denied(&self, perm: &impl Permeable, needle: &str, fields: &[DbField]) -> bool {
+        self.group(needle).is_some() && fields.iter().any(|f| perm.has_perm(f.permission).is_err())
+    }
+
+    /// The `fields` with the highest `DbField::priority` first, keeping the order otherwise.
+    fn by_priority<'a>(fields: impl Iterator<Item = &'a DbField>) -> Vec<&'a DbField> {
+        let mut fields = fields.collect::<Vec<_>>();
+        fields.sort_by_key(|f| std::cmp::Reverse(f.priority));
+        fields
+    }
+
     /// expr = { atom ~ (bin_op? ~ atom)* }
-    fn parse_expr(&self, perm: &impl Permeable, expr: Pair<Rule>) -> SuchResult {
+    ///
+    /// AND binds tighter than OR: `a AND b OR c` is `( a AND b ) OR c`.
+    fn parse_expr(&self, perm: &impl Permeable, expr: Pair<Rule>, depth: usize) -> SuchResult {
+        if depth > self.options.max_depth {
+            return Err(SuchError::TooDeep);
+        }
+        // terms joined by AND, `groups` holds the finished ones, separated by OR
+        let mut groups = Vec::new();
         let mut acc = Vec::new();
-        let mut or = false;
+        // an invert applies to the next atom only
         let mut comp_op = CompOp::Equal;
         for exp in expr.into_inner() {
-            //println!("** Suchbar::parse_expr:: {:?}", exp);
+            trace_pair!("parse_expr", exp);
             match exp.as_rule() {
                 Rule::field => {
-                    if let Ok(field) = self.parse_field(perm, exp, comp_op) {
-                        acc.push(field);
-                    }
+                    acc.push(self.parse_field(perm, exp, std::mem::take(&mut comp_op))?);
+                }
+                Rule::regex_field => {
+                    acc.push(self.parse_regex_field(perm, exp, std::mem::take(&mut comp_op))?);
                 }
-                Rule::or => or = true,
-                Rule::and => or = false,
+                Rule::or => groups.push(std::mem::take(&mut acc)),
+                Rule::and => {}
                 Rule::invert => comp_op = !comp_op,
-                Rule::term => acc.push(self.parse_term(perm, None, comp_op, exp)),
-                Rule::expr => acc.push(self.parse_expr(perm, exp)?),
-                _ => {
-                    println!("=> Suchbar::parse_expr:: {exp:?}");
+                Rule::term => {
+                    // `!value` matches if no field matches, so the whole group is negated
+                    let term = self.parse_term(perm, None, CompOp::Equal, exp)?;
+                    acc.push(if std::mem::take(&mut comp_op) == NotEqual {
+                        negate(term)
+                    } else {
+                        term
+                    });
                 }
+                Rule::expr => {
+                    let group = self.parse_expr(perm, exp, depth + 1)?;
+                    acc.push(if std::mem::take(&mut comp_op) == NotEqual {
+                        negate(group)
+                    } else {
+                        group
+                    });
+                }
+                _ => return Err(unexpected(&exp)),
             };
         }
-        if or {
-            Ok(OR(acc))
-        } else {
-            Ok(AND(acc))
+        if groups.is_empty() {
+            return Ok(AND(acc));
         }
+        groups.push(acc);
+        Ok(OR(groups
+            .into_iter()
+            .map(|mut group| {
+                if group.len() == 1 {
+                    group.remove(0)
+                } else {
+                    AND(group)
+                }
+            })
+            .collect()))
     }
 
     fn parse_field(&self, perm: &impl Permeable, expr: Pair<Rule>, not: CompOp) -> SuchResult {
@@ -133,22 +891,20 @@ impl Suchbar {
         let mut not = not == NotEqual;
         let mut comp_op = CompOp::default();
         for exp in expr.into_inner() {
-            // println!("!!! Suchbar::parse_field:: {exp:?}");
+            trace_pair!("parse_field", exp);
             match exp.as_rule() {
                 Rule::eq => comp_op = CompOp::from_str(exp.as_str()).unwrap_or_default(),
                 Rule::field_name => name = exp.as_str(),
                 Rule::invert => not = !not,
                 Rule::term => {
-                    return Ok(self.parse_term(
-                        perm,
-                        Some(name),
-                        if not { comp_op.not() } else { comp_op },
-                        exp,
-                    ));
-                }
-                _ => {
-                    println!("=> Suchbar::parse_field:: {exp:?}");
+                    let comp_op = if not { comp_op.not() } else { comp_op };
+                    // `group!=value` means no field of the group equals the value
+                    if comp_op == NotEqual && self.group(name).is_some() {
+                        return Ok(negate(self.parse_term(perm, Some(name), Equal, exp)?));
+                    }
+                    return self.parse_term(perm, Some(name), comp_op, exp);
                 }
+                _ => return Err(unexpected(&exp)),
             }
         }
         Err(SuchError::ParseError(format!(
@@ -156,25 +912,72 @@ impl Suchbar {
         )))
     }
 
-    fn parse_term(
+    fn parse_regex_field(
         &self,
         perm: &impl Permeable,
-        name: Option<&str>,
-        comp_op: CompOp,
         expr: Pair<Rule>,
-    ) -> SQLTerm {
-        use Direction::{From, To};
+        not: CompOp,
+    ) -> SuchResult {
+        let mut name = "";
+        let mut comp_op = CompOp::Match;
         let mut value = String::new();
-        let mut like_ending = false;
-        let mut like_starting = false;
-        let mut to_val = None;
         for exp in expr.into_inner() {
+            trace_pair!("parse_regex_field", exp);
             match exp.as_rule() {
-                Rule::starts_with => {
-                    if exp.as_str() == "*" {
-                        like_starting = true;
+                Rule::field_name => name = exp.as_str(),
+                Rule::matches => comp_op = CompOp::from_str(exp.as_str())?,
+                Rule::regex => {
+                    let quote = exp.as_str().chars().next().unwrap_or('"');
+                    value = match exp.into_inner().next() {
+                        // the quotes aren't part of the interior
+                        Some(v) if v.as_rule() == Rule::raw_string_interior => {
+                            unescape_quotes(v.as_str(), quote)
+                        }
+                        Some(v) => v.as_str().to_string(),
+                        None => String::new(),
+                    };
+                }
+                _ => return Err(unexpected(&exp)),
+            }
+        }
+        let comp_op = if not == NotEqual { !comp_op } else { comp_op };
+        let fields = self.choose_field_vec(name)?;
+        let group_denied = self.group_denied(perm, name, &fields);
+        Ok(OR(fields
+            .into_iter()
+            .map(|sf| {
+                if group_denied || perm.has_perm(sf.permission).is_err() {
+                    DENIED(sf)
+                } else {
+                    VALUE(sf, comp_op, Direction::From, value.clone())
+                }
+            })
+            .collect()))
+    }
+
+    fn parse_term(
+        &self,
+        perm: &impl Permeable,
+        name: Option<&str>,
+        comp_op: CompOp,
+        expr: Pair<Rule>,
+    ) -> SuchResult {
+        use Direction::{From, To};
+        let mut value = String::new();
+        let mut like_ending = false;
+        let mut like_starting = false;
+        // `^value$` is anchored at both ends, that's an exact match
+        let mut anchors = 0;
+        let mut to_val = None;
+        for exp in expr.into_inner() {
+            trace_pair!("parse_term", exp);
+            match exp.as_rule() {
+                Rule::starts_with => {
+                    if exp.as_str() == "*" {
+                        like_starting = true;
                     } else {
                         like_ending = true;
+                        anchors += 1;
                     }
                 }
                 Rule::ends_with => {
@@ -182,21 +985,32 @@ impl Suchbar {
                         like_ending = true;
                     } else {
                         like_starting = true;
+                        anchors += 1;
                     }
                 }
                 Rule::from_to => to_val = Self::parse_value(exp.into_inner().next().unwrap()),
                 Rule::value => value = Self::parse_value(exp).unwrap_or_default(),
                 Rule::date => value = exp.as_str().to_string(),
-                _ => println!("=> Suchbar::parse_term:: {exp:?}"),
+                _ => return Err(unexpected(&exp)),
             }
         }
 
-        OR(self
-            .choose_field_vec(name.unwrap_or_default())
+        let name_or_empty = name.unwrap_or_default();
+        let fields = self.choose_field_vec(name_or_empty)?;
+        let group_denied = self.group_denied(perm, name_or_empty, &fields);
+        Ok(OR(fields
             .into_iter()
             .map(|sf| {
-                if perm.has_perm(sf.permission).is_err() {
-                    DENIED
+                let value = self.unsigned(&sf, &value);
+                let to_val = to_val.as_deref().map(|v| self.unsigned(&sf, v));
+                if group_denied || perm.has_perm(sf.permission).is_err() {
+                    DENIED(sf)
+                } else if anchors == 2 {
+                    if comp_op == NotEqual {
+                        NOT(Box::new(VALUE(sf, Equal, From, value.to_string())))
+                    } else {
+                        VALUE(sf, comp_op, From, value.to_string())
+                    }
                 } else if like_ending || like_starting {
                     let value = match (like_starting, like_ending) {
                         (true, false) => format!("*{value}"),
@@ -210,68 +1024,174 @@ impl Suchbar {
                     }
                 } else if name.is_none() {
                     // list of terms means LIKE-search for text-fields.
+                    #[cfg(feature = "stemming")]
+                    let value = match self.options.stemming {
+                        Some(stemming) if sf.is_text() => stemming.stem(value),
+                        _ => value.to_string(),
+                    };
                     if sf.is_text() || self.options.like_in_numerics {
-                        LIKE(sf, format!("*{value}*"))
+                        if self.options.prefix_only {
+                            LIKE(sf, format!("{value}*"))
+                        } else {
+                            LIKE(sf, format!("*{value}*"))
+                        }
                     } else {
-                        VALUE(sf, Equal, From, value.clone())
+                        VALUE(sf, Equal, From, value.to_string())
                     }
-                } else if to_val.is_some() {
+                } else if let Some(to_val) = to_val {
+                    let upper = if self.options.inclusive_upper && sf.is_numeric() {
+                        CompOp::Lte
+                    } else {
+                        CompOp::Lt
+                    };
                     AND(vec![
-                        VALUE(sf.clone(), CompOp::Gte, From, value.clone()),
-                        VALUE(sf, CompOp::Lt, To, to_val.clone().unwrap_or_default()),
+                        VALUE(sf.clone(), CompOp::Gte, From, value.to_string()),
+                        VALUE(sf, upper, To, to_val.to_string()),
                     ])
                 } else if comp_op == NotEqual {
-                    NOT(Box::new(VALUE(sf, Equal, From, value.clone())))
+                    NOT(Box::new(VALUE(sf, Equal, From, value.to_string())))
                 } else {
-                    VALUE(sf, comp_op, From, value.clone())
+                    VALUE(sf, comp_op, From, value.to_string())
                 }
             })
-            .collect())
+            .collect()))
+    }
+
+    /// Numeric fields don't need the leading `+` of a value like `+49`. It's stripped, unless
+    /// `reject_plus_in_numerics` is set, then the value is refused by the field.
+    fn unsigned<'a>(&self, field: &DbField, value: &'a str) -> &'a str {
+        if field.is_numeric() && !self.options.reject_plus_in_numerics {
+            value.strip_prefix('+').unwrap_or(value)
+        } else {
+            value
+        }
     }
 
+    /// value = ${ uuid | raw_string | PUSH("\"" | "'") ~ raw_string_interior ~ POP }
+    ///
+    /// An unquoted value ends at whitespace, a space is part of it escaped as `\ `. A UUID is
+    /// one value, its dashes don't make it a range. A quoted value
+    /// is taken exactly as written between the quotes, but for its quote escaped as `\"` or `""`.
     fn parse_value(expr: Pair<Rule>) -> Option<String> {
+        let quote = expr.as_str().chars().next().unwrap_or('"');
         if let Some(exp) = expr.into_inner().next() {
+            trace_pair!("parse_value", exp);
             match exp.as_rule() {
-                Rule::raw_string => Some(exp.as_str().to_string()),
-                Rule::raw_string_interior => {
-                    // cut off surrounding quotes
-                    let (_, s) = exp.as_str().split_at(0);
-                    let (s, _) = s.split_at(s.len());
-                    Some(String::from(s))
-                }
-                _ => {
-                    println!("=> Suchbar::parse_value:: {exp:?}");
-                    None
-                }
+                Rule::uuid => Some(exp.as_str().to_string()),
+                Rule::raw_string => Some(exp.as_str().replace("\\ ", " ")),
+                // the quotes aren't part of the interior
+                Rule::raw_string_interior => Some(unescape_quotes(exp.as_str(), quote)),
+                _ => None,
             }
         } else {
             None
         }
     }
 
-    fn parse_sort(&self, sort: Pair<Rule>) -> Vec<SortField> {
-        let mut sort_fields = Vec::new();
-        let mut desc = false;
+    /// Appends the fields of a `;`-segment to `sort_fields`, a field already sorted by is skipped.
+    /// Unknown fields are left out, but a `further` segment naming no known field at all fails:
+    /// it's rather garbage than a sort.
+    ///
+    /// sort = { ";" ~ ( ","? ~ down? ~ field_name ~ ( descending | ascending )? )+ }
+    ///
+    /// A field sorts descending by a leading `^` or a following `desc`, `descending` or
+    /// `absteigend`. A `^` followed by `asc`, `ascending` or `aufsteigend` contradicts itself
+    /// and fails.
+    fn parse_sort(
+        &self,
+        sort: Pair<Rule>,
+        sort_fields: &mut Vec<SortField>,
+        further: bool,
+    ) -> Result<(), SuchError> {
+        let segment = sort.as_str().trim();
+        let mut known = false;
+        let mut down = false;
+        // the last field_name, if it had a `^` and if it was added to `sort_fields`
+        let mut last = ("", false, false);
         for so in sort.into_inner() {
             match so.as_rule() {
-                Rule::down => desc = true,
+                Rule::down => down = true,
                 Rule::field_name => {
-                    if let Some(field) = self.choose_field(so.as_str()) {
-                        sort_fields.push(SortField { desc, field });
-                        desc = false;
+                    let field = self.choose_field(so.as_str())?;
+                    known |= field.is_some();
+                    let field = field.filter(|field| {
+                        let column = field.column(None);
+                        !sort_fields.iter().any(|sf| sf.field.column(None) == column)
+                    });
+                    last = (so.as_str(), down, field.is_some());
+                    if let Some(field) = field {
+                        sort_fields.push(SortField { desc: down, field });
+                    }
+                    down = false;
+                }
+                Rule::ascending | Rule::descending => {
+                    let (name, caret, added) = last;
+                    let desc = so.as_rule() == Rule::descending;
+                    if caret && !desc {
+                        return Err(SuchError::ParseError(format!(
+                            "Conflicting sort order for '{name}'"
+                        )));
+                    }
+                    if let Some(sort_field) = sort_fields.last_mut().filter(|_| added) {
+                        sort_field.desc = desc;
                     }
                 }
                 _ => {}
             }
         }
-        sort_fields
+        if further && !known {
+            return Err(SuchError::ParseError(format!(
+                "No known field to sort by in '{segment}'"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Joins the `parts` by AND, in parentheses if there's more than one.
+fn and_group(mut parts: Vec<String>) -> String {
+    match parts.len() {
+        0 | 1 => parts.pop().unwrap_or_default(),
+        _ => format!("( {} )", parts.join(" AND ")),
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct WhereClause {
     sql_term: SQLTerm,
+    /// The terms on aggregate fields, see `DbField::aggregate`.
+    having_term: SQLTerm,
+    /// Columns given by the application, see `WhereClause::with_group_by`.
+    group_by: Vec<String>,
     sort_field: Vec<SortField>,
+    options: SuchOptions,
+    keyset: Option<Keyset>,
+    /// SQL-conditions given by the application, see `WhereClause::with_raw`.
+    raw: Vec<String>,
+}
+
+/// A tuple comparison like `( changed, id ) > ( '2022-01-01', '1000' )`, the values already
+/// SQL-literals.
+#[derive(Debug, Clone, PartialEq)]
+struct Keyset {
+    eq: CompOp,
+    columns: Vec<(DbField, String)>,
+}
+
+impl Keyset {
+    fn to_sql(&self, table: Option<&str>, q: char) -> String {
+        let (names, values): (Vec<_>, Vec<_>) = self
+            .columns
+            .iter()
+            .map(|(field, value)| (field.quoted_column(table, q), value.as_str()))
+            .unzip();
+        format!(
+            "( {} ) {} ( {} )",
+            names.join(", "),
+            self.eq,
+            values.join(", ")
+        )
+    }
 }
 
 impl WhereClause {
@@ -279,6 +1199,8 @@ impl WhereClause {
     /// Any Error will be ignored, then the returned String might be empty.
     ///
     /// Prefixes the return by `concatenate`, if parameter set, if empty omits.
+    /// The columns of `WhereClause::with_group_by` follow as ` GROUP BY …`, terms on aggregate
+    /// fields as ` HAVING …`, see `WhereClause::having`.
     ///
     /// # Example
     /// ```rust
@@ -301,18 +1223,204 @@ impl WhereClause {
     /// assert_eq!(" WHERE surname LIKE 'Don%' ORDER BY givenname, surname DESC", exec.to_sql("WHERE"));
     /// ```
     pub fn to_sql(&self, concatenate: impl Display) -> String {
-        let whr = self.where_clause().unwrap_or_default();
+        let sort = if self.sort_field.is_empty() {
+            String::new()
+        } else {
+            format!(" ORDER BY {}", self.order_by())
+        };
+        format!("{}{sort}", self.to_sql_unsorted(concatenate))
+    }
+
+    /// `to_sql` without the ORDER BY.
+    fn to_sql_unsorted(&self, concatenate: impl Display) -> String {
+        let whr = self.where_or_filters();
         let whr = if whr.is_empty() {
             whr
         } else {
             format!(" {concatenate} {whr}")
         };
-        let sort = if self.sort_field.is_empty() {
+        let group_by = self.group_by();
+        let group_by = if group_by.is_empty() {
             String::new()
         } else {
-            format!(" ORDER BY {}", self.order_by())
+            format!(" GROUP BY {}", group_by.join(", "))
+        };
+        let having = match self.having() {
+            Ok(having) if !having.is_empty() => format!(" HAVING {having}"),
+            _ => String::new(),
+        };
+        format!("{whr}{group_by}{having}")
+    }
+
+    /// Returns a complete query selecting the `columns` of `table`, with the WHERE and ORDER BY
+    /// of `to_sql`. Without `columns` all are selected.
+    ///
+    /// # Example
+    /// ```rust
+    /// use permeable::AllowAllPermission;
+    /// use suchbar::*;
+    /// use suchbar::DbType::TEXT;
+    ///
+    /// const SUCHBAR: Suchbar = Suchbar::new(&[
+    ///   DbField::new("surname", TEXT, "STD", &["surname", "sname", "sn"]),
+    ///   DbField::new("givenname", TEXT, "STD", &["givenname", "name", "n"])
+    /// ]);
+    ///
+    /// let exec = SUCHBAR.exec(&AllowAllPermission(), "sn=Don*; ^sn").unwrap();
+    /// assert_eq!(
+    ///     "SELECT surname, givenname FROM person WHERE surname LIKE 'Don%' ORDER BY surname DESC",
+    ///     exec.to_select(&["surname", "givenname"], "person")
+    /// );
+    /// ```
+    #[must_use]
+    pub fn to_select(&self, columns: &[&str], table: &str) -> String {
+        let columns = if columns.is_empty() {
+            String::from("*")
+        } else {
+            columns.join(", ")
         };
-        format!("{whr}{sort}")
+        format!("SELECT {columns} FROM {table}{}", self.to_sql("WHERE"))
+    }
+
+    /// Returns a query counting the rows of `table` matching the user-query, for the total of a
+    /// paginated result. Like `to_sql` errors are ignored, then all rows passing the raw filters
+    /// are counted. With a GROUP BY or a HAVING-clause the groups are counted, by a subquery.
+    ///
+    /// # Example
+    /// ```rust
+    /// use permeable::AllowAllPermission;
+    /// use suchbar::*;
+    /// use suchbar::DbType::TEXT;
+    ///
+    /// const SUCHBAR: Suchbar = Suchbar::new(&[
+    ///   DbField::new("surname", TEXT, "STD", &["surname", "sname", "sn"]),
+    /// ]);
+    ///
+    /// let exec = SUCHBAR.exec(&AllowAllPermission(), "sn=Don*; ^sn").unwrap();
+    /// assert_eq!(
+    ///     "SELECT COUNT(*) FROM person WHERE surname LIKE 'Don%'",
+    ///     exec.to_count_sql("person")
+    /// );
+    /// let exec = SUCHBAR.exec(&AllowAllPermission(), "; ^sn").unwrap();
+    /// assert_eq!("SELECT COUNT(*) FROM person", exec.to_count_sql("person"));
+    /// ```
+    #[must_use]
+    pub fn to_count_sql(&self, table: &str) -> String {
+        if !self.having_term.is_empty() || !self.group_by().is_empty() {
+            return format!(
+                "SELECT COUNT(*) FROM (SELECT 1 FROM {table}{}) AS counted",
+                self.to_sql_unsorted("WHERE")
+            );
+        }
+        let whr = self.where_or_filters();
+        if whr.is_empty() {
+            format!("SELECT COUNT(*) FROM {table}")
+        } else {
+            format!("SELECT COUNT(*) FROM {table} WHERE {whr}")
+        }
+    }
+
+    /// The query gives no condition: it's empty, has a sort only or all its terms were dropped
+    /// or denied. With `EmptyPolicy::MatchNothing` denied terms still emit `FALSE`, see
+    /// `WhereClause::has_denials`.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.keyset.is_none()
+            && self.raw.is_empty()
+            && self
+                .whole_term()
+                .to_sql_with(&self.options, None)
+                .map_or(true, |sql| sql.is_empty())
+    }
+
+    /// Some terms of the query were denied due to missing permissions.
+    #[must_use]
+    pub fn has_denials(&self) -> bool {
+        self.sql_term.has_denied() || self.having_term.has_denied()
+    }
+
+    /// The query has terms, but all of them were denied due to missing permissions. Unlike an
+    /// empty query that's handled by the `EmptyPolicy`, `FALSE` by default.
+    #[must_use]
+    pub fn all_denied(&self) -> bool {
+        matches!(
+            self.whole_term().to_sql_with(&self.options, None),
+            Err(SuchError::Denied(..))
+        )
+    }
+
+    /// The terms of the WHERE- and the HAVING-clause together.
+    fn whole_term(&self) -> Cow<'_, SQLTerm> {
+        if self.having_term.is_empty() {
+            Cow::Borrowed(&self.sql_term)
+        } else if self.sql_term.is_empty() {
+            Cow::Borrowed(&self.having_term)
+        } else {
+            Cow::Owned(AND(vec![self.sql_term.clone(), self.having_term.clone()]))
+        }
+    }
+
+    /// The comparisons of the query from left to right, like `price>10`, e.g. to log which fields
+    /// are searched. Denied terms and raw SQL are left out.
+    pub fn leaves(&self) -> impl Iterator<Item = LeafTerm<'_>> {
+        Leaves::new(&self.sql_term).chain(Leaves::new(&self.having_term))
+    }
+
+    /// Hands the parsed query to `visitor`, see `SqlTermVisitor`.
+    pub fn accept<V: SqlTermVisitor + ?Sized>(&self, visitor: &mut V) -> V::Output {
+        self.whole_term().accept(visitor)
+    }
+
+    /// Renders the query as a MongoDB query document: groups become `$and`/`$or`, comparisons
+    /// `{field: value}` or operators like `$gt`, similarity searches case-insensitive `$regex`.
+    /// Denied terms are handled like in the SQL, by `DeniedInAnd` and `EmptyPolicy`: `FALSE`
+    /// becomes `{"$expr": false}`, an empty query `{}`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use permeable::AllowAllPermission;
+    /// use suchbar::*;
+    /// use suchbar::DbType::{NUMERIC, TEXT};
+    ///
+    /// const SUCHBAR: Suchbar = Suchbar::new(&[
+    ///   DbField::new("name", TEXT, "STD", &["name", "n"]),
+    ///   DbField::new("price", NUMERIC(8, 2), "STD", &["price", "p"]),
+    /// ]);
+    ///
+    /// let exec = SUCHBAR.exec(&AllowAllPermission(), "n=*duck* p>10").unwrap();
+    /// assert_eq!(
+    ///   r#"{"$and":[{"name":{"$options":"i","$regex":"duck"}},{"price":{"$gt":10.0}}]}"#,
+    ///   exec.to_mongo().unwrap().to_string()
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Fails on raw SQL and on a keyset, MongoDB can't apply them. Like `where_clause` fails on
+    /// a query with no term rendered.
+    #[cfg(feature = "mongo")]
+    pub fn to_mongo(&self) -> Result<serde_json::Value, SuchError> {
+        if !self.raw.is_empty() || self.keyset.is_some() {
+            return Err(SuchError::ParseError(String::from(
+                "No raw SQL or keyset in MongoDB",
+            )));
+        }
+        let term = self.whole_term();
+        match term.accept(&mut MongoVisitor(&self.options)) {
+            Ok(doc) => Ok(doc),
+            Err(SuchError::Denied(..))
+                if self.options.empty_on_all_denied == EmptyPolicy::MatchNothing
+                    || !term.only_denied() =>
+            {
+                Ok(serde_json::json!({ "$expr": false }))
+            }
+            Err(SuchError::Denied(..))
+                if self.options.empty_on_all_denied == EmptyPolicy::MatchAll =>
+            {
+                Ok(serde_json::json!({}))
+            }
+            Err(_) if term.is_empty() => Ok(serde_json::json!({})),
+            Err(err) => Err(err),
+        }
     }
 
     /// Returns the WHERE-clause as SQL.
@@ -320,330 +1428,2609 @@ impl WhereClause {
     /// # Errors
     /// Failures in `query` can cause a `SuchError`.
     pub fn where_clause(&self) -> Result<String, SuchError> {
-        self.sql_term.to_sql()
+        self.render(None)
     }
 
-    /// Returns the SQL `ORDER BY` part.
+    /// Returns the HAVING-clause as SQL: the terms on aggregate fields, see `DbField::aggregate`.
+    /// `to_sql` emits it after the GROUP BY of `WhereClause::with_group_by`.
     ///
-    pub fn order_by(&self) -> String {
-        self.sort_field
-            .iter()
-            .map(SortField::to_sql)
-            .collect::<Vec<String>>()
-            .join(", ")
+    /// # Example
+    /// ```rust
+    /// use permeable::AllowAllPermission;
+    /// use suchbar::*;
+    /// use suchbar::DbType::{INTEGER, TEXT};
+    ///
+    /// const SUCHBAR: Suchbar = Suchbar::new(&[
+    ///   DbField::new("c.name", TEXT, "STD", &["name", "n"]),
+    ///   DbField::computed("order_count", "COUNT(o.id)", INTEGER(0, 1_000_000), "STD", &["orders"])
+    ///     .aggregate(),
+    /// ]);
+    ///
+    /// let exec = SUCHBAR.exec(&AllowAllPermission(), "n=Duck* orders>5").unwrap();
+    /// assert_eq!("c.name LIKE 'Duck%'", exec.where_clause().unwrap());
+    /// assert_eq!("(COUNT(o.id))>5", exec.having().unwrap());
+    /// ```
+    ///
+    /// # Errors
+    /// Like `where_clause`, e.g. if the query has no terms on aggregate fields.
+    pub fn having(&self) -> Result<String, SuchError> {
+        let sql = self.render_term(&self.having_term, self.options.table_prefix)?;
+        if cfg!(debug_assertions) {
+            check_balanced(&sql)?;
+        }
+        Ok(sql)
     }
-}
 
-#[cfg(test)]
-mod should {
-    use super::Suchbar;
-    use crate::db_field::DbField;
-    use crate::db_field::DbType::{INTEGER, NUMERIC, TEXT, VARCHAR};
-    use crate::suchbar::SuchOptions;
-    use crate::DbType::DATE;
-    use permeable::{Permeable, PermissionError};
+    /// Returns the WHERE-clause as SQL, every column prefixed by `table_alias`. This way the same
+    /// query can be used on both sides of a self-join.
+    ///
+    /// # Example
+    /// ```rust
+    /// use permeable::AllowAllPermission;
+    /// use suchbar::*;
+    /// use suchbar::DbType::{NUMERIC, TEXT};
+    ///
+    /// const SUCHBAR: Suchbar = Suchbar::new(&[
+    ///   DbField::new("name", TEXT, "STD", &["name", "n"]),
+    ///   DbField::new("price", NUMERIC(8, 2), "STD", &["price", "p"]),
+    /// ]);
+    ///
+    /// let exec = SUCHBAR.exec(&AllowAllPermission(), "n=Duck* p>10").unwrap();
+    /// assert_eq!("( a.name LIKE 'Duck%' AND a.price>10 )", exec.to_sql_aliased("a").unwrap());
+    /// assert_eq!("( b.name LIKE 'Duck%' AND b.price>10 )", exec.to_sql_aliased("b").unwrap());
+    /// ```
+    ///
+    /// # Errors
+    /// Failures in `query` can cause a `SuchError`.
+    pub fn to_sql_aliased(&self, table_alias: &str) -> Result<String, SuchError> {
+        self.render(Some(table_alias))
+    }
 
-    const SUCHBAR: Suchbar = Suchbar::new(&[
-        DbField::new(
-            "artikelnummer",
-            VARCHAR(18),
-            "READ_OFFER",
-            &["art", "artnr", "artikelnummer", "artikelnr", "ano"],
-        ),
-        DbField::new(
-            "positionstext",
-            TEXT,
-            "READ_OFFER",
-            &["beschreibung", "desc", "description", "ptext"],
-        ),
-        DbField::new(
-            "price",
-            NUMERIC(12, 2),
-            "READ_OFFER",
-            &["preis", "price", "p"],
-        ),
-        DbField::new("age", INTEGER(0, 150), "ACCESS_PRIVATE", &["alter", "age"]),
-        DbField::new(
-            "promille",
-            INTEGER(1, 1000),
-            "ACCESS_PRIVATE",
-            &["number", "nummer", "promille"],
-        ),
-        DbField::new("changed", DATE, "READ_OFFER", &["changed", "ch"]),
-    ]);
+    /// Combines this clause with `other` by AND, like the user's search with mandatory filters.
+    /// The sorting of `other` is appended, a field sorted by both keeps the direction of this
+    /// one. An empty clause is left out.
+    ///
+    /// # Example
+    /// ```rust
+    /// use permeable::AllowAllPermission;
+    /// use suchbar::*;
+    /// use suchbar::DbType::{INTEGER, TEXT};
+    ///
+    /// const SUCHBAR: Suchbar = Suchbar::new(&[
+    ///   DbField::new("name", TEXT, "STD", &["name", "n"]),
+    ///   DbField::new("tenant", INTEGER(0, 1000), "STD", &["tenant"]),
+    /// ]);
+    ///
+    /// let search = SUCHBAR.exec(&AllowAllPermission(), "n=Duck* OR n=Mouse; ^n").unwrap();
+    /// let filter = SUCHBAR.exec(&AllowAllPermission(), "tenant=7; n").unwrap();
+    /// assert_eq!(
+    ///     " WHERE ( ( name LIKE 'Duck%' OR name='Mouse' ) AND tenant=7 ) ORDER BY name DESC",
+    ///     search.and(filter).to_sql("WHERE")
+    /// );
+    /// ```
+    #[must_use]
+    pub fn and(self, other: WhereClause) -> Self {
+        self.merge(other, AND)
+    }
+
+    /// Combines this clause with `other` by OR, see `WhereClause::and`.
+    ///
+    /// # Errors
+    /// Fails if terms on aggregate fields would be combined with other terms, see
+    /// `DbField::aggregate`: the HAVING-clause can't be OR-ed with the WHERE-clause.
+    pub fn or(self, other: WhereClause) -> Result<Self, SuchError> {
+        let having = !self.having_term.is_empty() || !other.having_term.is_empty();
+        if having && !(self.sql_term.is_empty() && other.sql_term.is_empty()) {
+            return Err(SuchError::ParseError(String::from(
+                "Aggregate fields can only be combined by AND with other fields",
+            )));
+        }
+        Ok(self.merge(other, OR))
+    }
+
+    fn merge(self, other: WhereClause, group: fn(Vec<SQLTerm>) -> SQLTerm) -> Self {
+        let combine = |a: SQLTerm, b: SQLTerm| {
+            if a.is_empty() {
+                b
+            } else if b.is_empty() {
+                a
+            } else {
+                group(vec![a, b])
+            }
+        };
+        let mut sort_field = self.sort_field;
+        for sf in other.sort_field {
+            let column = sf.field.column(None);
+            if !sort_field.iter().any(|s| s.field.column(None) == column) {
+                sort_field.push(sf);
+            }
+        }
+        let mut group_by = self.group_by;
+        for column in other.group_by {
+            if !group_by.contains(&column) {
+                group_by.push(column);
+            }
+        }
+        Self {
+            sql_term: combine(self.sql_term, other.sql_term),
+            having_term: combine(self.having_term, other.having_term),
+            group_by,
+            sort_field,
+            options: self.options,
+            keyset: self.keyset.or(other.keyset),
+            raw: [self.raw, other.raw].concat(),
+        }
+    }
+
+    /// Adds the SQL-condition `raw_sql` by AND, for a mandatory filter the search-language can't
+    /// express, like `deleted_at IS NULL`. It's kept apart from the user's terms: it's emitted
+    /// even if they are denied or fail.
+    ///
+    /// **`raw_sql` is emitted as is, without any escaping. It must never contain user input!**
+    ///
+    /// # Example
+    /// ```rust
+    /// use permeable::AllowAllPermission;
+    /// use suchbar::*;
+    /// use suchbar::DbType::TEXT;
+    ///
+    /// const SUCHBAR: Suchbar = Suchbar::new(&[
+    ///   DbField::new("name", TEXT, "STD", &["name", "n"]),
+    /// ]);
+    ///
+    /// let mut exec = SUCHBAR.exec(&AllowAllPermission(), "n=Duck*").unwrap();
+    /// exec.with_raw("deleted_at IS NULL");
+    /// assert_eq!("( name LIKE 'Duck%' AND deleted_at IS NULL )", exec.where_clause().unwrap());
+    /// ```
+    pub fn with_raw(&mut self, raw_sql: &str) {
+        self.raw.push(raw_sql.to_string());
+    }
+
+    /// Groups the rows by the `columns`, emitted by `to_sql` between the WHERE- and the
+    /// HAVING-clause. Replaces the columns derived by `WhereClause::group_by`.
+    ///
+    /// **The `columns` are emitted as is, without any escaping. They must never contain user
+    /// input!**
+    ///
+    /// # Example
+    /// ```rust
+    /// use permeable::AllowAllPermission;
+    /// use suchbar::*;
+    /// use suchbar::DbType::{INTEGER, TEXT};
+    ///
+    /// const SUCHBAR: Suchbar = Suchbar::new(&[
+    ///   DbField::new("c.name", TEXT, "STD", &["name", "n"]),
+    ///   DbField::computed("order_count", "COUNT(o.id)", INTEGER(0, 1_000_000), "STD", &["orders"])
+    ///     .aggregate(),
+    /// ]);
+    ///
+    /// let mut exec = SUCHBAR.exec(&AllowAllPermission(), "n=Duck* orders>5; n").unwrap();
+    /// exec.with_group_by(&["c.id", "c.name"]);
+    /// assert_eq!(
+    ///     " WHERE c.name LIKE 'Duck%' GROUP BY c.id, c.name \
+    ///     HAVING (COUNT(o.id))>5 ORDER BY c.name",
+    ///     exec.to_sql("WHERE")
+    /// );
+    /// ```
+    pub fn with_group_by(&mut self, columns: &[&str]) {
+        self.group_by = columns.iter().map(ToString::to_string).collect();
+    }
+
+    /// The columns `to_sql` groups the rows by: the ones of `WhereClause::with_group_by`. Without
+    /// them, if the query filters or sorts by an aggregate field, the columns of the other fields
+    /// filtered or sorted by.
+    ///
+    /// # Example
+    /// ```rust
+    /// use permeable::AllowAllPermission;
+    /// use suchbar::*;
+    /// use suchbar::DbType::{INTEGER, TEXT};
+    ///
+    /// const SUCHBAR: Suchbar = Suchbar::new(&[
+    ///   DbField::new("c.name", TEXT, "STD", &["name", "n"]),
+    ///   DbField::new("c.city", TEXT, "STD", &["city"]),
+    ///   DbField::computed("order_count", "COUNT(o.id)", INTEGER(0, 1_000_000), "STD", &["orders"])
+    ///     .aggregate(),
+    /// ]);
+    ///
+    /// let exec = SUCHBAR.exec(&AllowAllPermission(), "n=Duck* orders>5; city").unwrap();
+    /// assert_eq!(vec!["c.name", "c.city"], exec.group_by());
+    /// assert_eq!(
+    ///     " WHERE c.name LIKE 'Duck%' GROUP BY c.name, c.city \
+    ///     HAVING (COUNT(o.id))>5 ORDER BY c.city",
+    ///     exec.to_sql("WHERE")
+    /// );
+    /// let exec = SUCHBAR.exec(&AllowAllPermission(), "n=Duck*; city").unwrap();
+    /// assert!(exec.group_by().is_empty());
+    /// ```
+    #[must_use]
+    pub fn group_by(&self) -> Vec<String> {
+        if !self.group_by.is_empty() {
+            return self.group_by.clone();
+        }
+        let sorted = self.sort_field.iter().map(|sf| &sf.field);
+        if self.having_term.is_empty() && !sorted.clone().any(|f| f.aggregate) {
+            return vec![];
+        }
+        let mut columns = vec![];
+        for field in self.leaves().map(|l| l.field).chain(sorted) {
+            let column =
+                field.quoted_column(self.options.table_prefix, self.options.value_quote.char());
+            if !field.aggregate && !columns.contains(&column) {
+                columns.push(column);
+            }
+        }
+        columns
+    }
+
+    /// Adds a comparison of the tuple of fields to the tuple of values, AND-ed with the query.
+    /// Meant for keyset pagination: the values of the last row of a page give the next page.
+    /// All comparisons have to be the same, values are compared as is, without wildcards.
+    /// Like the raw filters the keyset is emitted even if the user's terms are denied or fail.
+    ///
+    /// # Example
+    /// ```rust
+    /// use permeable::AllowAllPermission;
+    /// use suchbar::*;
+    /// use suchbar::DbType::{DATE, TEXT};
+    ///
+    /// const CHANGED: DbField = DbField::new("changed", DATE, "STD", &["changed"]);
+    /// const ART: DbField = DbField::new("artikelnummer", TEXT, "STD", &["art"]);
+    /// const SUCHBAR: Suchbar = Suchbar::new(&[CHANGED, ART]);
+    ///
+    /// let exec = SUCHBAR
+    ///     .exec(&AllowAllPermission(), "art=1*; changed, art")
+    ///     .unwrap()
+    ///     .with_keyset(&[
+    ///         (CHANGED, CompOp::Gt, String::from("2022-01-01")),
+    ///         (ART, CompOp::Gt, String::from("1000")),
+    ///     ])
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     "( artikelnummer LIKE '1%' AND ( changed, artikelnummer ) > ( '2022-01-01', '1000' ) )",
+    ///     exec.where_clause().unwrap()
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Fails on differing, regex or similarity comparisons and on values not fitting their field.
+    pub fn with_keyset(mut self, keyset: &[(DbField, CompOp, String)]) -> Result<Self, SuchError> {
+        let Some((_, eq, _)) = keyset.first() else {
+            self.keyset = None;
+            return Ok(self);
+        };
+        if matches!(
+            eq,
+            CompOp::Match | CompOp::NotMatch | CompOp::Similar | CompOp::NotSimilar
+        ) || keyset.iter().any(|(_, e, _)| e != eq)
+        {
+            return Err(SuchError::ParseError(String::from(
+                "Keyset needs the same comparison for all fields",
+            )));
+        }
+        let columns = keyset
+            .iter()
+            .map(|(field, _, value)| {
+                let literal = field.sql_literal(value, self.options.value_quote.char())?;
+                Ok((field.clone(), literal))
+            })
+            .collect::<Result<Vec<_>, SuchError>>()?;
+        self.keyset = Some(Keyset { eq: *eq, columns });
+        Ok(self)
+    }
+
+    /// The SQL of the term, a denied one according to the `EmptyPolicy`, and the keyset.
+    /// In debug builds unbalanced parentheses or quotes fail.
+    fn render(&self, table: Option<&str>) -> Result<String, SuchError> {
+        let sql = self.render_unchecked(table)?;
+        if cfg!(debug_assertions) {
+            check_balanced(&sql)?;
+        }
+        Ok(sql)
+    }
+
+    fn render_unchecked(&self, table: Option<&str>) -> Result<String, SuchError> {
+        let table = table.or(self.options.table_prefix);
+        let filters = self.filters(table);
+        let term = match self.render_term(&self.sql_term, table) {
+            Ok(term) => Some(term),
+            // the user's terms are left out, the filters still apply
+            Err(SuchError::Denied(..))
+                if self.options.empty_on_all_denied == EmptyPolicy::MatchAll
+                    && !filters.is_empty() =>
+            {
+                None
+            }
+            Err(_) if self.sql_term.is_empty() && !filters.is_empty() => None,
+            Err(err) => return Err(err),
+        };
+        Ok(and_group(term.into_iter().chain(filters).collect()))
+    }
+
+    /// The keyset and the raw filters, in SQL.
+    fn filters(&self, table: Option<&str>) -> Vec<String> {
+        self.keyset
+            .iter()
+            .map(|keyset| keyset.to_sql(table, self.options.value_quote.char()))
+            .chain(self.raw.iter().cloned())
+            .collect()
+    }
+
+    /// The WHERE-clause, or if the user's terms fail at least the keyset and the raw filters.
+    fn where_or_filters(&self) -> String {
+        self.where_clause()
+            .unwrap_or_else(|_| and_group(self.filters(self.options.table_prefix)))
+    }
+
+    /// The SQL of `term`, a denied one according to the `EmptyPolicy`.
+    fn render_term(&self, term: &SQLTerm, table: Option<&str>) -> Result<String, SuchError> {
+        // about 40 bytes per comparison, like `surname LIKE 'Duck%'` and its combinator
+        let mut sql = String::with_capacity(term.leaf_count() * 40);
+        match term.write_sql(&mut sql, &self.options, table) {
+            Ok(()) => Ok(sql),
+            Err(SuchError::Denied(..))
+                if self.options.empty_on_all_denied == EmptyPolicy::MatchNothing
+                    || !term.only_denied() =>
+            {
+                Ok(String::from("FALSE"))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Renders the query as understood back into the search-language, including the sorting.
+    ///
+    /// # Example
+    /// ```rust
+    /// use permeable::AllowAllPermission;
+    /// use suchbar::*;
+    /// use suchbar::DbType::TEXT;
+    ///
+    /// const SUCHBAR: Suchbar = Suchbar::new(&[
+    ///   DbField::new("surname", TEXT, "STD", &["surname", "sname", "sn"]),
+    ///   DbField::new("givenname", TEXT, "STD", &["givenname", "name", "n"])
+    /// ]);
+    ///
+    /// let exec = SUCHBAR.exec(&AllowAllPermission(), "sn = Don*  n=Duck; ^sn").unwrap();
+    /// assert_eq!("(surname=Don*&&givenname=Duck);^surname", exec.as_text(Style::Compact).unwrap());
+    /// assert_eq!(
+    ///     "( surname = Don* AND givenname = Duck ), sorted by surname descending",
+    ///     exec.as_text(Style::Pretty).unwrap()
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Fails if there is neither a term nor a sorting.
+    pub fn as_text(&self, style: Style) -> Result<String, SuchError> {
+        let term = self.whole_term().as_text(style);
+        if self.sort_field.is_empty() {
+            return term;
+        }
+        let sort = self
+            .sort_field
+            .iter()
+            .map(|sf| sf.as_text(style))
+            .collect::<Vec<String>>()
+            .join(", ");
+        Ok(match (term, style) {
+            (Ok(term), Style::Compact) => format!("{term};{sort}"),
+            (Err(_), Style::Compact) => format!(";{sort}"),
+            (Ok(term), _) => format!("{term}, sorted by {sort}"),
+            (Err(_), _) => format!("sorted by {sort}"),
+        })
+    }
+
+    /// Returns the SQL `ORDER BY` part, same as `order_by_safe`.
+    ///
+    pub fn order_by(&self) -> String {
+        self.order_by_safe()
+    }
+
+    /// Returns the SQL `ORDER BY` part, safe to be put into the SQL as is.
+    ///
+    /// Columns of an `ORDER BY` can't be bind parameters. Instead every column is taken from the
+    /// `DbField`s of the `Suchbar`: sort fields not found there are left out, the user can't
+    /// inject anything. Only the path of a `JSONB` field comes from the query, the grammar
+    /// allows letters, digits, `_` and `-` only there.
+    ///
+    /// # Example
+    /// ```rust
+    /// use permeable::AllowAllPermission;
+    /// use suchbar::*;
+    /// use suchbar::DbType::TEXT;
+    ///
+    /// const SUCHBAR: Suchbar = Suchbar::new(&[
+    ///   DbField::new("surname", TEXT, "STD", &["surname", "sname", "sn"]),
+    /// ]);
+    ///
+    /// let exec = SUCHBAR.exec(&AllowAllPermission(), ";password, ^sn").unwrap();
+    /// assert_eq!("surname DESC", exec.order_by_safe());
+    /// ```
+    pub fn order_by_safe(&self) -> String {
+        self.sort_field
+            .iter()
+            .map(|sf| sf.to_sql(&self.options))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod should {
+    use super::{DeniedInAnd, EmptyPolicy, Suchbar};
+    use crate::comp_op::CompOp;
+    use crate::db_field::DbField;
+    use crate::db_field::DbType::{BOOL, INTEGER, LTREE, NUMERIC, TEXT, VARCHAR};
+    use crate::error::SuchError;
+    use crate::explanation::TermExplanation;
+    use crate::style::Style;
+    use crate::suchbar::SuchOptions;
+    use crate::DbType::{DATE, ENUM, JSONB, TIMESTAMP, UUID};
+    use permeable::{Permeable, PermissionError};
+    use timewarp::Doy;
+
+    const SUCHBAR: Suchbar = Suchbar::new(&[
+        DbField::new(
+            "artikelnummer",
+            VARCHAR(18),
+            "READ_OFFER",
+            &["art", "artnr", "artikelnummer", "artikelnr", "ano"],
+        ),
+        DbField::new(
+            "positionstext",
+            TEXT,
+            "READ_OFFER",
+            &["beschreibung", "desc", "description", "ptext"],
+        ),
+        DbField::new(
+            "price",
+            NUMERIC(12, 2),
+            "READ_OFFER",
+            &["preis", "price", "p"],
+        ),
+        DbField::new("age", INTEGER(0, 150), "ACCESS_PRIVATE", &["alter", "age"]),
+        DbField::new(
+            "promille",
+            INTEGER(1, 1000),
+            "ACCESS_PRIVATE",
+            &["number", "nummer", "promille"],
+        ),
+        DbField::new("changed", DATE, "READ_OFFER", &["changed", "ch"]),
+    ]);
+
+    struct Perm {
+        perms: [&'static str; 2],
+    }
+
+    impl Permeable for Perm {
+        fn has_perm(&self, permission: &str) -> Result<(), PermissionError> {
+            if self.perms.contains(&permission) {
+                Ok(())
+            } else {
+                Err(PermissionError::denied(permission, "user"))
+            }
+        }
+    }
+
+    const ADMIN: Perm = Perm {
+        perms: ["ACCESS_PRIVATE", "READ_OFFER"],
+    };
+
+    const USER: Perm = Perm {
+        perms: ["ANYTHING_ELSE", "READ_OFFER"],
+    };
+
+    #[test]
+    fn give_permission() {
+        assert!(ADMIN.has_perm("READ_OFFER").is_ok());
+        assert!(ADMIN.has_perm("ACCESS_PRIVATE").is_ok());
+        assert!(USER.has_perm("READ_OFFER").is_ok());
+        assert!(USER.has_perm("ACCESS_PRIVATE").is_err());
+    }
+
+    #[test]
+    fn parse_not_equal_as_admin() {
+        let s = SUCHBAR
+            .exec(&ADMIN, "age!=123")
+            .expect("This should not panic!");
+        assert_eq!("  NOT age=123", s.to_sql(""));
+        let s = SUCHBAR
+            .exec(&ADMIN, "NOT age=123")
+            .expect("This should not panic!");
+        assert_eq!("  NOT age=123", s.to_sql(""));
+        let s = SUCHBAR
+            .exec(&ADMIN, "ptext!=A")
+            .expect("This should not panic!");
+        assert_eq!("  NOT positionstext='A'", s.to_sql(""));
+
+        let s = SUCHBAR
+            .exec(&ADMIN, " ptext != AAA*")
+            .expect("This should not panic!");
+        assert_eq!("  NOT positionstext LIKE 'AAA%'", s.to_sql(""));
+
+        let s = SUCHBAR
+            .exec(&ADMIN, "NOT ptext == AAA*")
+            .expect("This should not panic!");
+        assert_eq!("  NOT positionstext LIKE 'AAA%'", s.to_sql(""));
+
+        let s = SUCHBAR
+            .exec(&ADMIN, "NOT ptext != AAA*")
+            .expect("This should not panic!");
+        assert_eq!("  positionstext LIKE 'AAA%'", s.to_sql(""));
+    }
+
+    #[test]
+    fn parse_bang_as_not() {
+        for (query, sql) in [
+            ("!age=18", "NOT age=18"),
+            ("! age=18", "NOT age=18"),
+            ("NOT age=18", "NOT age=18"),
+            ("art=!2332", "NOT artikelnummer='2332'"),
+            ("!art!=2332", "artikelnummer='2332'"),
+            ("!(age=1 OR age=2)", "NOT ( age=1 OR age=2 )"),
+            ("NOT (age=1 OR age=2)", "NOT ( age=1 OR age=2 )"),
+            (
+                "!Eis",
+                "NOT ( artikelnummer LIKE '%Eis%' OR positionstext LIKE '%Eis%' )",
+            ),
+        ] {
+            let s = SUCHBAR.exec(&ADMIN, query).expect("This should not panic!");
+            assert_eq!(sql, s.where_clause().unwrap_or_default(), "{query}");
+        }
+    }
+
+    #[test]
+    fn parse_not_equal_apart_from_bang() {
+        let s = SUCHBAR
+            .exec(&ADMIN, "age!=18")
+            .expect("This should not panic!");
+        assert_eq!("NOT age=18", s.where_clause().unwrap_or_default());
+        let s = SUCHBAR
+            .exec(&ADMIN, "age != 18")
+            .expect("This should not panic!");
+        assert_eq!("NOT age=18", s.where_clause().unwrap_or_default());
+        // a `!` apart from the `=` is no comparator
+        assert!(SUCHBAR.exec(&ADMIN, "age ! = 18").is_err());
+        assert!(SUCHBAR.exec(&ADMIN, "age! =18").is_err());
+    }
+
+    #[test]
+    fn parse_every_comparator_spelling() {
+        for (query, sql) in [
+            ("age=18", "age=18"),
+            ("age==18", "age=18"),
+            ("age>18", "age>18"),
+            ("age>=18", "age>=18"),
+            ("age=>18", "age>=18"),
+            ("age<18", "age<18"),
+            ("age<=18", "age<=18"),
+            ("age=<18", "age<=18"),
+            ("age!=18", "NOT age=18"),
+            ("age=!18", "NOT age=18"),
+        ] {
+            let s = SUCHBAR.exec(&ADMIN, query).expect("This should not panic!");
+            assert_eq!(sql, s.where_clause().unwrap_or_default(), "{query}");
+            let spaced = query.replacen("age", "age ", 1).replacen("18", " 18", 1);
+            let s = SUCHBAR
+                .exec(&ADMIN, &spaced)
+                .expect("This should not panic!");
+            assert_eq!(sql, s.where_clause().unwrap_or_default(), "{spaced}");
+        }
+    }
+
+    #[test]
+    fn parse_single_ampersand_and_pipe() {
+        let s = SUCHBAR
+            .exec(&ADMIN, "ptext=a&b")
+            .expect("This should not panic!");
+        assert_eq!("  positionstext='a&b'", s.to_sql(""));
+        let s = SUCHBAR
+            .exec(&ADMIN, "ptext=a|b")
+            .expect("This should not panic!");
+        assert_eq!("  positionstext='a|b'", s.to_sql(""));
+        let s = SUCHBAR
+            .exec(&ADMIN, "ptext=a && ptext=b")
+            .expect("This should not panic!");
+        assert_eq!(
+            "  ( positionstext='a' AND positionstext='b' )",
+            s.to_sql("")
+        );
+        let s = SUCHBAR
+            .exec(&ADMIN, "ptext=a||ptext=b")
+            .expect("This should not panic!");
+        assert_eq!("  ( positionstext='a' OR positionstext='b' )", s.to_sql(""));
+    }
+
+    #[test]
+    fn parse_and_concat() {
+        let s = SUCHBAR
+            .exec(&ADMIN, "age=123 AND ptext=AAA")
+            .expect("This should not panic!");
+        assert_eq!(
+            "( age=123 AND positionstext='AAA' )",
+            s.where_clause().unwrap_or_default()
+        );
+        let s = SUCHBAR
+            .exec(&ADMIN, "art=123* AND ptext=AAA")
+            .expect("This should not panic!");
+        assert_eq!(
+            "( artikelnummer LIKE '123%' AND positionstext='AAA' )",
+            s.where_clause().unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn parse_not_equal_as_user() {
+        let s = SUCHBAR
+            .exec(&USER, "age!=123")
+            .expect("This should not panic!");
+        assert_eq!("  FALSE", s.to_sql(""));
+        let s = SUCHBAR
+            .exec(&USER, "NOT age=123")
+            .expect("This should not panic!");
+        assert_eq!("  FALSE", s.to_sql(""));
+        let s = SUCHBAR
+            .exec(&USER, "ptext!=A")
+            .expect("This should not panic!");
+        assert_eq!("  NOT positionstext='A'", s.to_sql(""));
+
+        let s = SUCHBAR
+            .exec(&USER, " ptext != AAA*")
+            .expect("This should not panic!");
+        assert_eq!("  NOT positionstext LIKE 'AAA%'", s.to_sql(""));
+
+        let s = SUCHBAR
+            .exec(&USER, "NOT ptext == AAA*")
+            .expect("This should not panic!");
+        assert_eq!("  NOT positionstext LIKE 'AAA%'", s.to_sql(""));
+
+        let s = SUCHBAR
+            .exec(&USER, "NOT ptext != AAA*")
+            .expect("This should not panic!");
+        assert_eq!("  positionstext LIKE 'AAA%'", s.to_sql(""));
+    }
+
+    #[test]
+    fn parse_integer_query_std() {
+        let s = SUCHBAR.exec(&ADMIN, "123").expect("This should not panic!");
+        assert_eq!(
+            "  ( artikelnummer LIKE '%123%' OR positionstext LIKE '%123%' OR \
+            price=123 OR age=123 OR promille=123 )",
+            s.to_sql("")
+        );
+        let s = SUCHBAR
+            .exec(&ADMIN, "1234")
+            .expect("This should not panic!");
+        assert_eq!(
+            "  ( artikelnummer LIKE '%1234%' OR positionstext LIKE '%1234%' \
+            OR price=1234 )",
+            s.to_sql("")
+        );
+    }
+
+    #[test]
+    fn parse_integer_query_like() {
+        let likebar =
+            Suchbar::new(SUCHBAR.db_fields).with_options(SuchOptions::new().like_in_numerics(true));
+        let s = likebar.exec(&ADMIN, "123").expect("This should not panic!");
+        assert_eq!(
+            "  ( artikelnummer LIKE '%123%' OR positionstext LIKE '%123%' OR price::TEXT LIKE '%123%' \
+            OR age::TEXT LIKE '%123%' OR promille::TEXT LIKE '%123%' )",
+            s.to_sql("")
+        );
+        let s = likebar
+            .exec(&ADMIN, "1234")
+            .expect("This should not panic!");
+        assert_eq!(
+            "  ( artikelnummer LIKE '%1234%' OR positionstext LIKE '%1234%' OR \
+            price::TEXT LIKE '%1234%' )",
+            s.to_sql("")
+        );
+    }
+
+    #[test]
+    fn parse_like_query() {
+        let s = SUCHBAR
+            .exec(&ADMIN, "art='2332*'")
+            .expect("This should not panic!");
+        assert_eq!("  artikelnummer LIKE '2332%'", s.to_sql(""));
+        let s = SUCHBAR
+            .exec(&ADMIN, "art=2332*")
+            .expect("This should not panic!");
+        assert_eq!("  artikelnummer LIKE '2332%'", s.to_sql(""));
+        let s = SUCHBAR
+            .exec(&ADMIN, "art=2332$")
+            .expect("This should not panic!");
+        assert_eq!("  artikelnummer LIKE '%2332'", s.to_sql(""));
+        let s = SUCHBAR
+            .exec(&ADMIN, "art='*2332*'")
+            .expect("This should not panic!");
+        assert_eq!("  artikelnummer LIKE '%2332%'", s.to_sql(""));
+        let s = SUCHBAR
+            .exec(&ADMIN, "art=^'2332'")
+            .expect("This should not panic!");
+        assert_eq!("  artikelnummer LIKE '2332%'", s.to_sql(""));
+        let s = SUCHBAR
+            .exec(&ADMIN, "art=^2332$")
+            .expect("This should not panic!");
+        assert_eq!("  artikelnummer='2332'", s.to_sql(""));
+        let s = SUCHBAR
+            .exec(&ADMIN, "art!=^'23 32'$")
+            .expect("This should not panic!");
+        assert_eq!("  NOT artikelnummer='23 32'", s.to_sql(""));
+        let s = SUCHBAR
+            .exec(&ADMIN, "^2332$")
+            .expect("This should not panic!");
+        assert_eq!(
+            "  ( artikelnummer='2332' OR positionstext='2332' OR price=2332 )",
+            s.to_sql("")
+        );
+    }
+
+    #[test]
+    fn parse_misc_query() {
+        let query = r#"ano!=23342 AND (desc=^"irgend ein langer Text!" OR price='35,12'); artnr, ^nummer, age"#;
+        let s = SUCHBAR.exec(&ADMIN, query).expect("This should not panic!");
+        assert_eq!(
+            "  ( NOT artikelnummer='23342' AND ( positionstext LIKE 'irgend ein langer Text!%' \
+            OR price=35.12 ) ) ORDER BY artikelnummer, promille DESC, age",
+            s.to_sql("")
+        );
+    }
+
+    #[test]
+    fn parse_from_to_values() {
+        let s = SUCHBAR
+            .exec(&ADMIN, "age=10-19")
+            .expect("This should not panic!");
+        assert_eq!("  ( age>=10 AND age<19 )", s.to_sql(""));
+    }
+
+    #[test]
+    fn parse_like_somewhere() {
+        let s = SUCHBAR
+            .exec(&ADMIN, "*Superman*")
+            .expect("This should not panic!");
+        assert_eq!(
+            " WHERE ( artikelnummer LIKE '%Superman%' OR positionstext LIKE '%Superman%' )",
+            s.to_sql("WHERE")
+        );
+
+        let s = SUCHBAR
+            .exec(&ADMIN, "Superman Batman")
+            .expect("This should not panic!");
+        assert_eq!(
+            " WHERE ( ( artikelnummer LIKE '%Superman%' OR positionstext LIKE '%Superman%' ) AND \
+            ( artikelnummer LIKE '%Batman%' OR positionstext LIKE '%Batman%' ) )",
+            s.to_sql("WHERE")
+        );
+        // age = *5*
+        let s = SUCHBAR
+            .exec(&ADMIN, "artnr = *5*")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE artikelnummer LIKE '%5%'", s.to_sql("WHERE"));
+    }
+
+    #[test]
+    fn parse_iso_dates() {
+        let s = SUCHBAR
+            .exec(&ADMIN, "ch=2022-12-24")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE changed='2022-12-24'", s.to_sql("WHERE"));
+
+        let s = SUCHBAR
+            .exec(&ADMIN, r#"ch="2022-12-24""#)
+            .expect("This should not panic!");
+        assert_eq!(" WHERE changed='2022-12-24'", s.to_sql("WHERE"));
+    }
+
+    #[test]
+    fn parse_natural_language_dates() {
+        let year = Doy::today().year;
+        let s = SUCHBAR
+            .exec(&ADMIN, "ch=Jan")
+            .expect("This should not panic!");
+        assert_eq!(format!(" WHERE changed='{year}-01-01'"), s.to_sql("WHERE"));
+
+        let s = SUCHBAR
+            .exec(&ADMIN, r#"ch=24.12.2022"#)
+            .expect("This should not panic!");
+        assert_eq!(" WHERE changed='2022-12-24'", s.to_sql("WHERE"));
+        let s = SUCHBAR
+            .exec(&ADMIN, r#"ch='Feb'-'Dez'"#)
+            .expect("This should not panic!");
+        assert_eq!(
+            format!(
+                " WHERE ( changed>='{year}-02-01' AND changed<'{}-01-01' )",
+                year + 1
+            ),
+            s.to_sql("WHERE")
+        );
+        let s = SUCHBAR
+            .exec(&ADMIN, r#"ch=Feb-Dez"#)
+            .expect("This should not panic!");
+        assert_eq!(
+            format!(
+                " WHERE ( changed>='{year}-02-01' AND changed<'{}-01-01' )",
+                year + 1
+            ),
+            s.to_sql("WHERE")
+        );
+    }
+
+    #[test]
+    fn list_sort_by_fields() {
+        let s = SUCHBAR
+            .exec(&ADMIN, ";age, art")
+            .expect("This should not panic!");
+        assert_eq!(" ORDER BY age, artikelnummer", s.to_sql("WHERE"));
+        let s = SUCHBAR
+            .exec(&ADMIN, ";art, ^p, ch")
+            .expect("This should not panic!");
+        assert_eq!(
+            " ORDER BY artikelnummer, price DESC, changed",
+            s.to_sql("WHERE")
+        );
+    }
+
+    #[test]
+    fn reject_too_long_query() {
+        let shortbar =
+            Suchbar::new(SUCHBAR.db_fields).with_options(SuchOptions::new().max_query_len(16));
+        let s = shortbar
+            .exec(&ADMIN, "art=123")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE artikelnummer='123'", s.to_sql("WHERE"));
+
+        let query = "(".repeat(1_000_000);
+        assert!(matches!(
+            shortbar.exec(&ADMIN, query),
+            Err(SuchError::TooLong)
+        ));
+    }
+
+    #[test]
+    fn reject_too_deep_query() {
+        let flatbar = Suchbar::new(SUCHBAR.db_fields).with_options(SuchOptions::new().max_depth(2));
+        let s = flatbar
+            .exec(&ADMIN, "((art=1 OR art=2))")
+            .expect("This should not panic!");
+        assert_eq!(
+            " WHERE ( artikelnummer='1' OR artikelnummer='2' )",
+            s.to_sql("WHERE")
+        );
+        assert!(matches!(
+            flatbar.exec(&ADMIN, "(((art=1)))"),
+            Err(SuchError::TooDeep)
+        ));
+
+        let query = format!("{}art=1{}", "(".repeat(100), ")".repeat(100));
+        assert!(matches!(
+            SUCHBAR.exec(&ADMIN, query),
+            Err(SuchError::TooDeep)
+        ));
+    }
+
+    #[test]
+    fn sort_case_insensitive() {
+        let lowerbar = Suchbar::new(SUCHBAR.db_fields)
+            .with_options(SuchOptions::new().case_insensitive_sort(true));
+        let s = lowerbar
+            .exec(&ADMIN, ";ptext, ^art, p, ch")
+            .expect("This should not panic!");
+        assert_eq!(
+            " ORDER BY LOWER(positionstext), LOWER(artikelnummer) DESC, price, changed",
+            s.to_sql("WHERE")
+        );
+    }
+
+    #[test]
+    fn parse_prefix_only() {
+        let prefixbar =
+            Suchbar::new(SUCHBAR.db_fields).with_options(SuchOptions::new().prefix_only(true));
+        let s = prefixbar
+            .exec(&ADMIN, "Superman")
+            .expect("This should not panic!");
+        assert_eq!(
+            " WHERE ( artikelnummer LIKE 'Superman%' OR positionstext LIKE 'Superman%' )",
+            s.to_sql("WHERE")
+        );
+        let s = prefixbar
+            .exec(&ADMIN, "art=2332*")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE artikelnummer LIKE '2332%'", s.to_sql("WHERE"));
+        let s = prefixbar
+            .exec(&ADMIN, "*Superman*")
+            .expect("This should not panic!");
+        assert_eq!(
+            " WHERE ( artikelnummer LIKE '%Superman%' OR positionstext LIKE '%Superman%' )",
+            s.to_sql("WHERE")
+        );
+    }
+
+    #[test]
+    fn resolve_ambiguous_alias() {
+        const AKTBAR: Suchbar = Suchbar::new(&[
+            DbField::new("aktiv", BOOL, "READ_OFFER", &["akt", "aktiv"]),
+            DbField::new("ma_active", VARCHAR(32), "READ_OFFER", &["akt", "ma"]),
+        ]);
+        let s = AKTBAR
+            .exec(&ADMIN, "akt=true")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE aktiv", s.to_sql("WHERE"));
+
+        let strictbar = Suchbar::new(AKTBAR.db_fields)
+            .with_options(SuchOptions::new().ambiguous_alias_error(true));
+        let s = strictbar
+            .exec(&ADMIN, "ma=Micha")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE ma_active='Micha'", s.to_sql("WHERE"));
+        match strictbar.exec(&ADMIN, "akt=true") {
+            Err(SuchError::AmbiguousAlias(alias, candidates)) => {
+                assert_eq!("akt", alias);
+                assert_eq!(vec!["aktiv", "ma_active"], candidates);
+            }
+            other => panic!("Expected AmbiguousAlias, got {other:?}"),
+        }
+        assert!(strictbar.exec(&ADMIN, ";akt").is_err());
+    }
+
+    #[test]
+    fn explain_query() {
+        let explanation = SUCHBAR
+            .explain_query(&USER, "art!=12* ptext=AAA age>18")
+            .expect("This should not panic!");
+        assert_eq!(
+            vec![
+                TermExplanation {
+                    field: "artikelnummer",
+                    operator: "NOT LIKE".into(),
+                    value: "12*".into(),
+                    denied: false,
+                    allowed: &[],
+                },
+                TermExplanation {
+                    field: "positionstext",
+                    operator: "=".into(),
+                    value: "AAA".into(),
+                    denied: false,
+                    allowed: &[],
+                },
+                TermExplanation {
+                    field: "age",
+                    operator: String::new(),
+                    value: String::new(),
+                    denied: true,
+                    allowed: &[],
+                },
+            ],
+            explanation.terms
+        );
+    }
+
+    #[test]
+    fn parse_ltree_path() {
+        const TREEBAR: Suchbar = Suchbar::new(&[DbField::new(
+            "cat",
+            LTREE,
+            "READ_OFFER",
+            &["cat", "category"],
+        )]);
+        let s = TREEBAR
+            .exec(&ADMIN, "cat=electronics.phones")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE cat <@ 'electronics.phones'", s.to_sql("WHERE"));
+        let s = TREEBAR
+            .exec(&ADMIN, "cat=electronics.*")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE cat ~ 'electronics.*'", s.to_sql("WHERE"));
+    }
+
+    #[test]
+    fn where_clause_as_text() {
+        let s = SUCHBAR
+            .exec(&ADMIN, "art!=12* AND (p>=3,5 OR age=10-19); ^p, age")
+            .expect("This should not panic!");
+        assert_eq!(
+            "(art!=12*&&(preis>=3,5||(alter>=10&&alter<19)));^preis, alter",
+            s.as_text(Style::Compact).unwrap()
+        );
+        assert_eq!(
+            "( art != 12* AND ( preis >= 3,5 OR ( alter >= 10 AND alter < 19 ) ) ), \
+            sorted by preis descending, alter",
+            s.as_text(Style::Pretty).unwrap()
+        );
+        assert_eq!(
+            "( `art` != 12\\* **&&** ( `preis` >= 3,5 **||** ( `alter` >= 10 **&&** `alter` < 19 ) ) ), \
+            sorted by `preis` descending, `alter`",
+            s.as_text(Style::Markdown).unwrap()
+        );
+        let s = SUCHBAR
+            .exec(&ADMIN, "NOT age<18; ^art")
+            .expect("This should not panic!");
+        assert_eq!(
+            "<span class=\"field_name\">alter</span> <span class=\"eq\">&gt;=</span> \
+            <span class=\"value\">18</span>, sorted by <span class=\"field_name\">art</span> \
+            <span class=\"down\">descending</span>",
+            s.as_text(Style::Html).unwrap()
+        );
+        let s = SUCHBAR
+            .exec(&ADMIN, ";art")
+            .expect("This should not panic!");
+        assert_eq!(";art", s.as_text(Style::Compact).unwrap());
+        assert_eq!("sorted by art", s.as_text(Style::Pretty).unwrap());
+        let s = SUCHBAR
+            .exec(&USER, "age=5")
+            .expect("This should not panic!");
+        assert!(s.as_text(Style::Pretty).is_err());
+    }
+
+    #[test]
+    fn parse_plus_prefixed_values() {
+        const PHONEBAR: Suchbar = Suchbar::new(&[
+            DbField::new("phone", VARCHAR(20), "READ_OFFER", &["phone", "tel"]),
+            DbField::new("age", INTEGER(0, 150), "READ_OFFER", &["age"]),
+        ]);
+        let s = PHONEBAR
+            .exec(&ADMIN, "phone=+4915112345678")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE phone='+4915112345678'", s.to_sql("WHERE"));
+        let s = PHONEBAR
+            .exec(&ADMIN, "tel=+49151* + age=+18")
+            .expect("This should not panic!");
+        assert_eq!(
+            " WHERE ( phone LIKE '+49151%' AND age=18 )",
+            s.to_sql("WHERE")
+        );
+        let s = PHONEBAR
+            .exec(&ADMIN, "+49")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE ( phone LIKE '%+49%' OR age=49 )", s.to_sql("WHERE"));
+
+        let strictbar = Suchbar::new(PHONEBAR.db_fields)
+            .with_options(SuchOptions::new().reject_plus_in_numerics(true));
+        let s = strictbar
+            .exec(&ADMIN, "+49")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE phone LIKE '%+49%'", s.to_sql("WHERE"));
+        let s = strictbar
+            .exec(&ADMIN, "age=+18")
+            .expect("This should not panic!");
+        assert!(s.where_clause().is_err());
+    }
+
+    #[test]
+    fn parse_unaccent() {
+        let unaccentbar =
+            Suchbar::new(SUCHBAR.db_fields).with_options(SuchOptions::new().unaccent(true));
+        let s = unaccentbar
+            .exec(&ADMIN, "cafe art=12")
+            .expect("This should not panic!");
+        assert_eq!(
+            " WHERE ( ( unaccent(artikelnummer) ILIKE unaccent('%cafe%') OR \
+            unaccent(positionstext) ILIKE unaccent('%cafe%') ) AND artikelnummer='12' )",
+            s.to_sql("WHERE")
+        );
+    }
+
+    #[test]
+    fn expand_macros() {
+        const MACROBAR: Suchbar = Suchbar::new(SUCHBAR.db_fields).with_macros(&[
+            ("cheap", "p<10"),
+            ("bargain", "@cheap AND ptext=*sale*"),
+            ("ping", "art=1 OR @pong"),
+            ("pong", "@ping"),
+        ]);
+        let s = MACROBAR
+            .exec(&ADMIN, "@cheap AND art=5*")
+            .expect("This should not panic!");
+        assert_eq!(
+            " WHERE ( price<10 AND artikelnummer LIKE '5%' )",
+            s.to_sql("WHERE")
+        );
+        let s = MACROBAR
+            .exec(&ADMIN, "@Bargain OR ptext='mail@home'")
+            .expect("This should not panic!");
+        assert_eq!(
+            " WHERE ( ( price<10 AND positionstext LIKE '%sale%' ) OR positionstext='mail@home' )",
+            s.to_sql("WHERE")
+        );
+        assert!(matches!(
+            MACROBAR.exec(&ADMIN, "@ping"),
+            Err(SuchError::TooDeep)
+        ));
+        assert!(matches!(
+            MACROBAR.exec(&ADMIN, "@expensive"),
+            Err(SuchError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn where_clause_aliased() {
+        let s = SUCHBAR
+            .exec(&ADMIN, "art=5* AND !ptext=Super AND (p>=2 OR age<18)")
+            .expect("This should not panic!");
+        assert_eq!(
+            "( a.artikelnummer LIKE '5%' AND NOT a.positionstext='Super' AND ( a.price>=2 OR a.age<18 ) )",
+            s.to_sql_aliased("a").expect("This should not panic!")
+        );
+        assert_eq!(
+            "( artikelnummer LIKE '5%' AND NOT positionstext='Super' AND ( price>=2 OR age<18 ) )",
+            s.where_clause().expect("This should not panic!")
+        );
+    }
+
+    #[test]
+    fn parse_regex() {
+        let s = SUCHBAR
+            .exec(&ADMIN, "ptext~^Super.*")
+            .expect("This should not panic!");
+        assert_eq!(
+            "positionstext ~ '^Super.*'",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = SUCHBAR
+            .exec(
+                &ADMIN,
+                "(art~it's !ptext~\"^(Super|Duper) [0-9]+$\") art!~^5",
+            )
+            .expect("This should not panic!");
+        assert_eq!(
+            "( ( artikelnummer ~ 'it''s' AND positionstext !~ '^(Super|Duper) [0-9]+$' ) AND artikelnummer !~ '^5' )",
+            s.where_clause().expect("This should not panic!")
+        );
+        assert_eq!(
+            "((art~\"it's\"&&beschreibung!~\"^(Super|Duper) [0-9]+$\")&&art!~\"^5\")",
+            s.as_text(Style::Compact).expect("This should not panic!")
+        );
+        let s = SUCHBAR
+            .exec(&ADMIN, "p~^1.*")
+            .expect("This should not panic!");
+        assert!(s.where_clause().is_err());
+        for query in ["ptext~'a''b'", r"ptext~'a\'b'", r#"ptext~"a'b""#] {
+            let s = SUCHBAR.exec(&ADMIN, query).expect("This should not panic!");
+            assert_eq!(
+                "positionstext ~ 'a''b'",
+                s.where_clause().expect("This should not panic!"),
+                "{query}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_wildcard_field_names() {
+        const WILDCARD: Suchbar = Suchbar::new(SUCHBAR.db_fields)
+            .with_options(SuchOptions::new().wildcard_field_names(true));
+        let s = WILDCARD
+            .exec(&ADMIN, "pr*=5")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE ( price=5 OR promille=5 )", s.to_sql("WHERE"));
+        let s = WILDCARD
+            .exec(&ADMIN, "de*=Super* Art*=1")
+            .expect("This should not panic!");
+        assert_eq!(
+            " WHERE ( positionstext LIKE 'Super%' AND artikelnummer='1' )",
+            s.to_sql("WHERE")
+        );
+        assert!(matches!(
+            SUCHBAR.exec(&ADMIN, "pr*=5"),
+            Err(SuchError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn parse_json_path() {
+        const JSONBAR: Suchbar = Suchbar::new(&[
+            DbField::new("meta", JSONB, "ACCESS_PRIVATE", &["meta", "m"]),
+            DbField::new("price", NUMERIC(12, 2), "READ_OFFER", &["price", "p"]),
+        ]);
+        let s = JSONBAR
+            .exec(&ADMIN, "m.color=red m.dim.Width>2 ;^meta.color")
+            .expect("This should not panic!");
+        assert_eq!(
+            " WHERE ( meta->>'color'='red' AND (meta->'dim'->>'Width')::numeric>2 ) ORDER BY meta->>'color' DESC",
+            s.to_sql("WHERE")
+        );
+        assert_eq!(
+            "(meta.color=red&&meta.dim.Width>2);^meta.color",
+            s.as_text(Style::Compact).expect("This should not panic!")
+        );
+        let s = JSONBAR
+            .exec(&USER, "meta.color=red")
+            .expect("This should not panic!");
+        assert_eq!("FALSE", s.where_clause().expect("This should not panic!"));
+        assert!(JSONBAR.exec(&ADMIN, "p.color=red").is_err());
+    }
+
+    #[test]
+    fn explain_enum() {
+        const STATUSBAR: Suchbar = Suchbar::new(&[DbField::new(
+            "status",
+            ENUM(&["active", "closed"]),
+            "READ_OFFER",
+            &["status", "st"],
+        )]);
+        let s = STATUSBAR
+            .exec(&USER, "st=Active")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE status='active'", s.to_sql("WHERE"));
+        let explanation = STATUSBAR
+            .explain_query(&USER, "st=open")
+            .expect("This should not panic!");
+        assert_eq!(&["active", "closed"], explanation.terms[0].allowed);
+        assert_eq!(
+            "[status, st] ENUM (active, closed)\n",
+            STATUSBAR.explanation(&USER)
+        );
+        assert_eq!(
+            &["active", "closed"],
+            STATUSBAR.fields_for(&USER)[0].allowed
+        );
+    }
+
+    #[test]
+    fn wrap_single_groups() {
+        const WRAPBAR: Suchbar = Suchbar::new(SUCHBAR.db_fields)
+            .with_options(SuchOptions::new().wrap_single_groups(true));
+        let s = WRAPBAR
+            .exec(&ADMIN, "art=5")
+            .expect("This should not panic!");
+        assert_eq!(
+            "( ( artikelnummer='5' ) )",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = WRAPBAR
+            .exec(&ADMIN, "art=5 age>18")
+            .expect("This should not panic!");
+        assert_eq!(
+            "( ( artikelnummer='5' ) AND ( age>18 ) )",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = SUCHBAR
+            .exec(&ADMIN, "art=5")
+            .expect("This should not panic!");
+        assert_eq!(
+            "artikelnummer='5'",
+            s.where_clause().expect("This should not panic!")
+        );
+    }
+
+    #[test]
+    fn parse_enum_by_index() {
+        const STATUSBAR: Suchbar = Suchbar::new(&[DbField::new(
+            "status",
+            ENUM(&["active", "in_progress", "closed"]),
+            "READ_OFFER",
+            &["status", "st"],
+        )])
+        .with_options(SuchOptions::new().enum_by_index(true));
+        let s = STATUSBAR
+            .exec(&USER, "st=2 OR st=Closed")
+            .expect("This should not panic!");
+        assert_eq!(
+            " WHERE ( status='in_progress' OR status='closed' )",
+            s.to_sql("WHERE")
+        );
+        for query in ["st=0", "st=4"] {
+            let s = STATUSBAR
+                .exec(&USER, query)
+                .expect("This should not panic!");
+            assert!(s.where_clause().is_err());
+        }
+        let s = STATUSBAR
+            .with_options(SuchOptions::new())
+            .exec(&USER, "st=2")
+            .expect("This should not panic!");
+        assert!(s.where_clause().is_err());
+    }
+
+    #[test]
+    fn parse_and_before_or() {
+        let s = SUCHBAR
+            .exec(&ADMIN, "art=1 AND ptext=a OR age=2")
+            .expect("This should not panic!");
+        assert_eq!(
+            "( ( artikelnummer='1' AND positionstext='a' ) OR age=2 )",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = SUCHBAR
+            .exec(&ADMIN, "art=1 OR ptext=a AND age=2")
+            .expect("This should not panic!");
+        assert_eq!(
+            "( artikelnummer='1' OR ( positionstext='a' AND age=2 ) )",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = SUCHBAR
+            .exec(&ADMIN, "art=1 ptext=a || age=2 && p=3 OR art=4")
+            .expect("This should not panic!");
+        assert_eq!(
+            "( ( artikelnummer='1' AND positionstext='a' ) OR ( age=2 AND price=3 ) OR artikelnummer='4' )",
+            s.where_clause().expect("This should not panic!")
+        );
+    }
+
+    #[test]
+    fn parse_denied_in_and() {
+        let s = SUCHBAR
+            .exec(&USER, "p<10 AND age>18")
+            .expect("This should not panic!");
+        assert_eq!("FALSE", s.where_clause().expect("This should not panic!"));
+        let match_all =
+            SUCHBAR.with_options(SuchOptions::new().empty_on_all_denied(EmptyPolicy::MatchAll));
+        let s = match_all
+            .exec(&USER, "p<10 AND age>18")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE FALSE", s.to_sql("WHERE"));
+        let s = match_all
+            .exec(&USER, "age>18")
+            .expect("This should not panic!");
+        assert_eq!("", s.to_sql("WHERE"));
+        let s = SUCHBAR
+            .exec(&USER, "(p<10 AND age>18) OR art=1")
+            .expect("This should not panic!");
+        assert_eq!(
+            "artikelnummer='1'",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = SUCHBAR
+            .exec(&USER, "p<10 AND (age>18 OR art=1)")
+            .expect("This should not panic!");
+        assert_eq!(
+            "( price<10 AND artikelnummer='1' )",
+            s.where_clause().expect("This should not panic!")
+        );
+
+        let s = SUCHBAR
+            .with_options(SuchOptions::new().denied_in_and(DeniedInAnd::Drop))
+            .exec(&USER, "p<10 AND age>18")
+            .expect("This should not panic!");
+        assert_eq!(
+            "price<10",
+            s.where_clause().expect("This should not panic!")
+        );
+    }
+
+    #[test]
+    fn iterate_leaves() {
+        let s = SUCHBAR
+            .exec(&USER, "(art=5* p>10 age>18) OR NOT (p<=3 OR art!=7)")
+            .expect("This should not panic!");
+        let leaves = s
+            .leaves()
+            .map(|l| (l.field.db_name, l.operator, l.value))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("artikelnummer", "LIKE", "5*"),
+                ("price", ">", "10"),
+                ("price", ">", "3"),
+                ("artikelnummer", "=", "7"),
+            ],
+            leaves
+        );
+        let s = SUCHBAR
+            .exec(&USER, "age>18; ^art")
+            .expect("This should not panic!");
+        assert_eq!(0, s.leaves().count());
+    }
+
+    #[test]
+    fn split_aggregates_into_having() {
+        const ORDERBAR: Suchbar = Suchbar::new(&[
+            DbField::new("c.name", TEXT, "READ_OFFER", &["name", "n"]),
+            DbField::computed(
+                "order_count",
+                "COUNT(o.id)",
+                INTEGER(0, 1_000_000),
+                "READ_OFFER",
+                &["orders", "oc"],
+            )
+            .aggregate(),
+        ]);
+        let s = ORDERBAR
+            .exec(&USER, "n=Duck* oc>5 (oc<100 OR NOT oc=50); ^n")
+            .expect("This should not panic!");
+        assert_eq!(
+            "c.name LIKE 'Duck%'",
+            s.where_clause().expect("This should not panic!")
+        );
+        assert_eq!(
+            "( (COUNT(o.id))>5 AND ( (COUNT(o.id))<100 OR NOT (COUNT(o.id))=50 ) )",
+            s.having().expect("This should not panic!")
+        );
+        assert_eq!(
+            " WHERE c.name LIKE 'Duck%' GROUP BY c.name HAVING ( (COUNT(o.id))>5 AND \
+            ( (COUNT(o.id))<100 OR NOT (COUNT(o.id))=50 ) ) ORDER BY c.name DESC",
+            s.to_sql("WHERE")
+        );
+        assert_eq!(vec!["c.name"], s.group_by());
+        assert_eq!(3, s.leaves().filter(|l| l.field.aggregate).count());
+
+        // field-free terms skip aggregates
+        let s = ORDERBAR.exec(&USER, "12").expect("This should not panic!");
+        assert_eq!(" WHERE c.name LIKE '%12%'", s.to_sql("WHERE"));
+        assert!(s.having().is_err());
+
+        let s = ORDERBAR
+            .exec(&USER, "oc>5")
+            .expect("This should not panic!");
+        assert_eq!(" HAVING (COUNT(o.id))>5", s.to_sql("WHERE"));
+        assert!(!s.is_empty());
+
+        let s = ORDERBAR
+            .exec(&USER, "n=Duck; ^oc")
+            .expect("This should not panic!");
+        assert_eq!(
+            " WHERE c.name='Duck' GROUP BY c.name ORDER BY (COUNT(o.id)) DESC",
+            s.to_sql("WHERE")
+        );
+
+        assert!(ORDERBAR.exec(&USER, "oc>5 OR n=Duck").is_err());
+    }
+
+    #[test]
+    fn group_by_in_order() {
+        const ORDERBAR: Suchbar = Suchbar::new(&[
+            DbField::new("c.name", TEXT, "READ_OFFER", &["name", "n"]),
+            DbField::computed(
+                "order_count",
+                "COUNT(o.id)",
+                INTEGER(0, 1_000_000),
+                "READ_OFFER",
+                &["orders", "oc"],
+            )
+            .aggregate(),
+        ]);
+        let exec = |query: &str| {
+            let mut exec = ORDERBAR.exec(&USER, query).expect("This should not panic!");
+            exec.with_group_by(&["c.id", "c.name"]);
+            exec
+        };
+        assert_eq!(
+            " AND c.name='Duck' GROUP BY c.id, c.name HAVING (COUNT(o.id))>5 ORDER BY c.name DESC",
+            exec("n=Duck oc>5; ^n").to_sql("AND")
+        );
+        assert_eq!(
+            " GROUP BY c.id, c.name HAVING (COUNT(o.id))>5",
+            exec("oc>5").to_sql("AND")
+        );
+        assert_eq!(
+            " GROUP BY c.id, c.name ORDER BY c.name",
+            exec("; n").to_sql("AND")
+        );
+        let mut other = exec("n=Duck");
+        other.with_group_by(&["c.name", "c.city"]);
+        assert_eq!(
+            " WHERE c.name='Duck' GROUP BY c.id, c.name, c.city HAVING (COUNT(o.id))>5",
+            exec("oc>5").and(other).to_sql("WHERE")
+        );
+        assert_eq!(
+            "SELECT COUNT(*) FROM (SELECT 1 FROM c WHERE c.name='A' \
+            GROUP BY c.id, c.name HAVING (COUNT(o.id))>5) AS counted",
+            exec("n=A oc>5; n").to_count_sql("c")
+        );
+        let plain = ORDERBAR.exec(&USER, "n=A").expect("This should not panic!");
+        assert_eq!(
+            "SELECT COUNT(*) FROM c WHERE c.name='A'",
+            plain.to_count_sql("c")
+        );
+        assert!(exec("n=A oc>5").or(exec("n=B oc<2")).is_err());
+        assert!(exec("n=A").or(exec("oc<2")).is_err());
+        assert_eq!(
+            " GROUP BY c.id, c.name HAVING ( (COUNT(o.id))>5 OR (COUNT(o.id))<2 )",
+            exec("oc>5")
+                .or(exec("oc<2"))
+                .expect("This should not panic!")
+                .to_sql("WHERE")
+        );
+    }
+
+    #[test]
+    fn write_sql_into_buffer() {
+        let mut buf = String::from("WHERE ");
+        let s = SUCHBAR
+            .exec(&USER, "(p<10 AND age>18) OR art=1 OR NOT p>5")
+            .expect("This should not panic!");
+        s.sql_term
+            .to_sql_into(&mut buf)
+            .expect("This should not panic!");
+        assert_eq!("WHERE ( artikelnummer='1' OR price<=5 )", buf);
+        let s = SUCHBAR
+            .exec(&USER, "p<10 AND age>18")
+            .expect("This should not panic!");
+        assert!(s.sql_term.to_sql_into(&mut buf).is_err());
+        assert_eq!("WHERE ( artikelnummer='1' OR price<=5 )", buf);
+    }
+
+    #[test]
+    fn parse_normalized_field_names() {
+        const ORDERBAR: Suchbar = Suchbar::new(&[
+            DbField::new("order_date", DATE, "READ_OFFER", &["order_date", "od"]),
+            DbField::new("order_text", TEXT, "READ_OFFER", &["order_text"]),
+        ])
+        .with_options(SuchOptions::new().normalize_field_names(true));
+        let s = ORDERBAR
+            .exec(
+                &USER,
+                "Order Date>=2024-01-01 order-text = 'order date=x' order  text!=a",
+            )
+            .expect("This should not panic!");
+        assert_eq!(
+            "( order_date>='2024-01-01' AND order_text='order date=x' AND NOT order_text='a' )",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = ORDERBAR
+            .exec(&USER, "order text")
+            .expect("This should not panic!");
+        assert_eq!(
+            "( order_text LIKE '%order%' AND order_text LIKE '%text%' )",
+            s.where_clause().expect("This should not panic!")
+        );
+    }
+
+    #[test]
+    fn order_by_known_fields_only() {
+        let s = SUCHBAR
+            .exec(&ADMIN, ";password, art, pg_sleep")
+            .expect("This should not panic!");
+        assert_eq!("artikelnummer", s.order_by_safe());
+        assert_eq!(" ORDER BY artikelnummer", s.to_sql("WHERE"));
+        let s = SUCHBAR
+            .exec(&ADMIN, ";unknown")
+            .expect("This should not panic!");
+        assert_eq!("", s.to_sql("WHERE"));
+        assert!(SUCHBAR.exec(&ADMIN, ";art; DROP TABLE x").is_err());
+        assert!(SUCHBAR.exec(&ADMIN, ";unknown; art").is_ok());
+        assert!(SUCHBAR.exec(&ADMIN, ";art'--").is_err());
+    }
+
+    #[test]
+    fn parse_negated_group() {
+        let s = SUCHBAR
+            .exec(&ADMIN, "NOT (art=1 OR art=2)")
+            .expect("This should not panic!");
+        assert_eq!(
+            "NOT ( artikelnummer='1' OR artikelnummer='2' )",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = SUCHBAR
+            .exec(&ADMIN, "!(art=1 art=2) OR p=3")
+            .expect("This should not panic!");
+        assert_eq!(
+            "( NOT ( artikelnummer='1' AND artikelnummer='2' ) OR price=3 )",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = SUCHBAR
+            .exec(&ADMIN, "!(!(art=1 OR art=2))")
+            .expect("This should not panic!");
+        assert_eq!(
+            "( artikelnummer='1' OR artikelnummer='2' )",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = SUCHBAR
+            .exec(&ADMIN, "!(art=1)")
+            .expect("This should not panic!");
+        assert_eq!(
+            "NOT artikelnummer='1'",
+            s.where_clause().expect("This should not panic!")
+        );
+    }
+
+    #[test]
+    fn empty_on_all_denied() {
+        let s = SUCHBAR
+            .exec(&USER, "age>18 OR promille=5")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE FALSE", s.to_sql("WHERE"));
+        let s = SUCHBAR
+            .exec(&USER, "age>18 OR art=5")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE artikelnummer='5'", s.to_sql("WHERE"));
+
+        let match_all =
+            SUCHBAR.with_options(SuchOptions::new().empty_on_all_denied(EmptyPolicy::MatchAll));
+        let s = match_all
+            .exec(&USER, "age>18 OR promille=5")
+            .expect("This should not panic!");
+        assert_eq!("", s.to_sql("WHERE"));
+
+        let error =
+            SUCHBAR.with_options(SuchOptions::new().empty_on_all_denied(EmptyPolicy::Error));
+        assert_eq!(
+            "denied: field 'age' requires ACCESS_PRIVATE",
+            error
+                .exec(&USER, "age>18 OR promille=5")
+                .expect_err("This should fail!")
+                .to_string()
+        );
+        assert!(error.exec(&USER, "age>18 OR art=5").is_ok());
+        assert!(error.exec(&USER, ";age").is_ok());
+    }
+
+    #[test]
+    fn validate_query() {
+        assert!(SUCHBAR
+            .validate(&ADMIN, "art=5* (p<10 OR age>18) Eisen; ^art")
+            .is_ok());
+        let errors = SUCHBAR
+            .validate(&USER, "colour=red age>18 p<ten Eisen; ^size")
+            .expect_err("This should fail!");
+        assert_eq!(4, errors.len());
+        assert!(matches!(&errors[0], SuchError::UnknownField(name) if name == "colour"));
+        assert!(matches!(&errors[1], SuchError::UnknownField(name) if name == "size"));
+        assert!(matches!(
+            errors[2],
+            SuchError::Denied("age", "ACCESS_PRIVATE")
+        ));
+        assert!(
+            matches!(&errors[3], SuchError::ParseError(msg) if msg == "No Numeric value 'ten'")
+        );
+        let errors = SUCHBAR
+            .validate(&ADMIN, "art=(5")
+            .expect_err("This should fail!");
+        assert_eq!(1, errors.len());
+        let errors = SUCHBAR
+            .with_options(SuchOptions::new().strict_fields(true))
+            .validate(&ADMIN, "colour=red")
+            .expect_err("This should fail!");
+        assert_eq!(1, errors.len());
+        assert!(matches!(&errors[0], SuchError::UnknownField(name) if name == "colour"));
+    }
+
+    #[cfg(feature = "stemming")]
+    #[test]
+    fn parse_stemmed() {
+        use crate::stemming::Stemming;
+        const STEMBAR: Suchbar = Suchbar::new(SUCHBAR.db_fields)
+            .with_options(SuchOptions::new().stemming(Stemming::English));
+        let s = STEMBAR
+            .exec(&ADMIN, "Running 'happy cats' ptext=running")
+            .expect("This should not panic!");
+        assert_eq!(
+            "( ( artikelnummer LIKE '%Run%' OR positionstext LIKE '%Run%' ) AND \
+            ( artikelnummer LIKE '%happy cat%' OR positionstext LIKE '%happy cat%' ) AND \
+            positionstext='running' )",
+            s.where_clause().expect("This should not panic!")
+        );
+    }
+
+    #[cfg(feature = "mongo")]
+    #[test]
+    fn render_mongo() {
+        let s = SUCHBAR
+            .exec(&ADMIN, r"(art=A\*B* OR p<=5,5) NOT ptext=x.y age!=3 Duck")
+            .expect("This should not panic!");
+        assert_eq!(
+            serde_json::json!({ "$and": [
+                { "$or": [
+                    { "artikelnummer": { "$regex": "^A\\*B", "$options": "i" } },
+                    { "price": { "$lte": 5.5 } },
+                ] },
+                { "positionstext": { "$ne": "x.y" } },
+                { "age": { "$ne": 3 } },
+                { "$or": [
+                    { "artikelnummer": { "$regex": "Duck", "$options": "i" } },
+                    { "positionstext": { "$regex": "Duck", "$options": "i" } },
+                ] },
+            ] }),
+            s.to_mongo().expect("This should not panic!")
+        );
+        let mongo = |suchbar: &Suchbar, query: &str| {
+            suchbar
+                .exec(&USER, query)
+                .expect("This should not panic!")
+                .to_mongo()
+                .expect("This should not panic!")
+        };
+        assert_eq!(
+            serde_json::json!({ "$expr": false }),
+            mongo(&SUCHBAR, "age=3")
+        );
+        assert_eq!(serde_json::json!({}), mongo(&SUCHBAR, "; ^art"));
+        assert_eq!(
+            serde_json::json!({ "$expr": false }),
+            mongo(&SUCHBAR, "art=x age=3")
+        );
+        let drop = SUCHBAR.with_options(SuchOptions::new().denied_in_and(DeniedInAnd::Drop));
+        assert_eq!(
+            serde_json::json!({ "artikelnummer": "x" }),
+            mongo(&drop, "art=x age=3")
+        );
+        let match_all =
+            SUCHBAR.with_options(SuchOptions::new().empty_on_all_denied(EmptyPolicy::MatchAll));
+        assert_eq!(serde_json::json!({}), mongo(&match_all, "age=3"));
+
+        let today = format!("{:#}", Doy::today());
+        assert_eq!(
+            serde_json::json!({ "changed": today }),
+            mongo(&SUCHBAR, "ch=heute")
+        );
+        assert_eq!(
+            serde_json::json!({ "changed": { "$gt": "2020-01-01" } }),
+            mongo(&SUCHBAR, "ch>1.1.2020")
+        );
+        assert_eq!(
+            serde_json::json!({ "$and": [
+                { "changed": { "$gte": "2024-01-01" } },
+                { "changed": { "$lt": "2024-01-31" } },
+            ] }),
+            mongo(&SUCHBAR, "ch='2024-01-01'..'2024-01-31'")
+        );
+
+        let mut s = SUCHBAR
+            .exec(&USER, "art=x")
+            .expect("This should not panic!");
+        s.with_raw("tenant_id = 7");
+        assert!(s.to_mongo().is_err());
+    }
+
+    #[test]
+    fn parse_escaped_wildcards() {
+        let s = SUCHBAR
+            .exec(&ADMIN, r"art=A\*B")
+            .expect("This should not panic!");
+        assert_eq!(
+            "artikelnummer='A*B'",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = SUCHBAR
+            .exec(&ADMIN, r"art=A\*B*")
+            .expect("This should not panic!");
+        assert_eq!(
+            "artikelnummer LIKE 'A*B%'",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = SUCHBAR
+            .exec(&ADMIN, r"art='A\?B*'")
+            .expect("This should not panic!");
+        assert_eq!(
+            "artikelnummer LIKE 'A?B%'",
+            s.where_clause().expect("This should not panic!")
+        );
+    }
+
+    #[test]
+    fn parse_inner_wildcards() {
+        for (query, expected) in [
+            ("art=12*34", "artikelnummer LIKE '12%34'"),
+            ("art=1*2*3", "artikelnummer LIKE '1%2%3'"),
+            ("art=*1*2*", "artikelnummer LIKE '%1%2%'"),
+            ("art=1**2", "artikelnummer LIKE '1%%2'"),
+            ("art!=12*34", "NOT artikelnummer LIKE '12%34'"),
+        ] {
+            let s = SUCHBAR.exec(&ADMIN, query).expect("This should not panic!");
+            assert_eq!(
+                expected,
+                s.where_clause().expect("This should not panic!"),
+                "{query}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_hidden_fields() {
+        const HIDDENBAR: Suchbar = Suchbar::new(&[
+            DbField::new("name", TEXT, "READ_OFFER", &["name"]),
+            DbField::new("notes", TEXT, "READ_OFFER", &["notes"]).hidden(),
+        ]);
+        let s = HIDDENBAR
+            .exec(&USER, "foo")
+            .expect("This should not panic!");
+        assert_eq!(
+            "name LIKE '%foo%'",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = HIDDENBAR
+            .exec(&USER, "notes=foo*")
+            .expect("This should not panic!");
+        assert_eq!(
+            "notes LIKE 'foo%'",
+            s.where_clause().expect("This should not panic!")
+        );
+    }
+
+    #[test]
+    fn parse_search_type() {
+        const VIEWBAR: Suchbar =
+            Suchbar::new(&[
+                DbField::new("zip", INTEGER(0, 99999), "READ_OFFER", &["zip"]).searched_as(TEXT),
+            ]);
+        let s = VIEWBAR
+            .exec(&USER, "zip=01*")
+            .expect("This should not panic!");
+        assert_eq!(
+            "zip LIKE '01%'",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = VIEWBAR
+            .exec(&USER, "zip=0815")
+            .expect("This should not panic!");
+        assert_eq!(
+            "zip='0815'",
+            s.where_clause().expect("This should not panic!")
+        );
+        assert_eq!("NUMBER", VIEWBAR.db_fields[0].db_type());
+    }
+
+    #[test]
+    fn parse_fields_by_priority() {
+        const PRIOBAR: Suchbar = Suchbar::new(&[
+            DbField::new("notes", TEXT, "READ_OFFER", &["notes"]),
+            DbField::new("name", TEXT, "READ_OFFER", &["name"]).priority(10),
+        ]);
+        let s = PRIOBAR.exec(&USER, "foo").expect("This should not panic!");
+        assert_eq!(
+            "( name LIKE '%foo%' OR notes LIKE '%foo%' )",
+            s.where_clause().expect("This should not panic!")
+        );
+        assert_eq!("[name] TEXT\n[notes] TEXT\n", PRIOBAR.explanation(&USER));
+    }
+
+    #[test]
+    fn explain_restricted_fields() {
+        const AGEBAR: Suchbar = Suchbar::new(&[
+            DbField::new("name", TEXT, "READ_OFFER", &["name", "n"]),
+            DbField::new("age", INTEGER(0, 150), "ACCESS_PRIVATE", &["age"]),
+        ]);
+        assert_eq!("[name, n] TEXT\n", AGEBAR.explanation(&USER));
+        assert_eq!(
+            "[name, n] TEXT\n[age] NUMBER (requires ACCESS_PRIVATE)\n",
+            AGEBAR.explanation_full(&USER)
+        );
+        assert_eq!(
+            "[name, n] TEXT\n[age] NUMBER\n",
+            AGEBAR.explanation_full(&ADMIN)
+        );
+        assert_eq!(AGEBAR.explanation(&ADMIN), AGEBAR.explanation_full(&ADMIN));
+    }
+
+    #[test]
+    fn parse_sort_keywords() {
+        for (query, expected) in [
+            ("age>18; p desc, art", "price DESC, artikelnummer"),
+            ("age>18; p descending", "price DESC"),
+            (
+                "age>18; p ABSTEIGEND, art aufsteigend",
+                "price DESC, artikelnummer",
+            ),
+            ("age>18; ^p asc", ""),
+            ("age>18; p asc, ^art", "price, artikelnummer DESC"),
+            ("age>18; ^p desc", "price DESC"),
+            ("age>18; p, desc", "price, positionstext"),
+        ] {
+            match SUCHBAR.exec(&ADMIN, query) {
+                Ok(s) => assert_eq!(expected, s.order_by(), "{query}"),
+                Err(e) => assert_eq!("Conflicting sort order for 'p'", e.to_string(), "{query}"),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_sort_segments() {
+        for (query, expected) in [
+            ("age>18; p, p", "price"),
+            ("age>18; ^p, price, art", "price DESC, artikelnummer"),
+            (
+                "age>18; p, art desc, price desc",
+                "price, artikelnummer DESC",
+            ),
+            ("age>18; p; ^art", "price, artikelnummer DESC"),
+            ("; ^p ;art; p, age", "price DESC, artikelnummer, age"),
+        ] {
+            let s = SUCHBAR.exec(&ADMIN, query).expect("This should not panic!");
+            assert_eq!(expected, s.order_by(), "{query}");
+        }
+    }
+
+    #[test]
+    fn parse_default_sort() {
+        const SORTBAR: Suchbar =
+            Suchbar::new(SUCHBAR.db_fields).with_options(SuchOptions::new().default_sort(&[
+                ("changed", true),
+                ("art", false),
+                ("unknown", true),
+            ]));
+        let s = SORTBAR
+            .exec(&ADMIN, "age>18")
+            .expect("This should not panic!");
+        assert_eq!(
+            " WHERE age>18 ORDER BY changed DESC, artikelnummer",
+            s.to_sql("WHERE")
+        );
+        let s = SORTBAR.exec(&ADMIN, "").expect("This should not panic!");
+        assert_eq!(" ORDER BY changed DESC, artikelnummer", s.to_sql("WHERE"));
+        let s = SORTBAR
+            .exec(&ADMIN, "age>18; ^p")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE age>18 ORDER BY price DESC", s.to_sql("WHERE"));
+        let s = SUCHBAR
+            .exec(&ADMIN, "age>18")
+            .expect("This should not panic!");
+        assert_eq!(" WHERE age>18", s.to_sql("WHERE"));
+
+        const SAMEBAR: Suchbar = Suchbar::new(&[
+            DbField::new("name", TEXT, "READ_OFFER", &["name", "x"]),
+            DbField::new("name", VARCHAR(20), "READ_OFFER", &["short", "x"]),
+        ])
+        .with_options(
+            SuchOptions::new()
+                .ambiguous_alias_error(true)
+                .default_sort(&[("x", false), ("name", false), ("short", true)]),
+        );
+        let s = SAMEBAR.exec(&USER, "").expect("This should not panic!");
+        assert_eq!(" ORDER BY name", s.to_sql("WHERE"));
+    }
+
+    #[test]
+    fn keyset() {
+        let field = |name: &str| {
+            SUCHBAR
+                .db_fields
+                .iter()
+                .find(|f| f.db_name == name)
+                .cloned()
+                .expect("This should not panic!")
+        };
+        let s = SUCHBAR
+            .exec(&ADMIN, "; changed, p")
+            .expect("This should not panic!")
+            .with_keyset(&[
+                (field("changed"), CompOp::Gte, String::from("2022-01-01")),
+                (field("price"), CompOp::Gte, String::from("10,50")),
+            ])
+            .expect("This should not panic!");
+        assert_eq!(
+            " WHERE ( a.changed, a.price ) >= ( '2022-01-01', 10.50 ) ORDER BY changed, price",
+            format!(
+                " WHERE {} ORDER BY {}",
+                s.to_sql_aliased("a").expect("This should not panic!"),
+                s.order_by()
+            )
+        );
+        assert!(SUCHBAR
+            .exec(&ADMIN, "art=1*")
+            .expect("This should not panic!")
+            .with_keyset(&[
+                (field("changed"), CompOp::Gt, String::from("2022-01-01")),
+                (field("price"), CompOp::Lt, String::from("10")),
+            ])
+            .is_err());
+        assert!(SUCHBAR
+            .exec(&ADMIN, "art=1*")
+            .expect("This should not panic!")
+            .with_keyset(&[(field("price"), CompOp::Gt, String::from("1' OR 1=1"))])
+            .is_err());
+        let s = SUCHBAR
+            .with_options(SuchOptions::new().empty_on_all_denied(EmptyPolicy::MatchAll))
+            .exec(&USER, "age=5; p")
+            .expect("This should not panic!")
+            .with_keyset(&[(field("price"), CompOp::Gt, String::from("10"))])
+            .expect("This should not panic!");
+        assert_eq!(
+            "( price ) > ( 10 )",
+            s.where_clause().expect("This should not panic!")
+        );
+        assert_eq!(
+            " WHERE ( price ) > ( 10 ) ORDER BY price",
+            s.to_sql("WHERE")
+        );
+    }
+
+    #[test]
+    fn parse_time_of_day() {
+        const TIMEBAR: Suchbar = Suchbar::new(&[DbField::new(
+            "changed",
+            TIMESTAMP,
+            "READ_OFFER",
+            &["changed", "ch"],
+        )]);
+        let s = TIMEBAR
+            .exec(&USER, "ch.time=09:00-17:00")
+            .expect("This should not panic!");
+        assert_eq!(
+            "( changed::time>='09:00:00' AND changed::time<'17:00:00' )",
+            s.where_clause().expect("This should not panic!")
+        );
+        assert_eq!(
+            "(changed.time>=09:00&&changed.time<17:00)",
+            s.as_text(Style::Compact).expect("This should not panic!")
+        );
+        let s = TIMEBAR
+            .exec(&USER, "ch.TIME>=8:30:15 ;^ch.time")
+            .expect("This should not panic!");
+        assert_eq!(
+            " WHERE changed::time>='08:30:15' ORDER BY changed::time DESC",
+            s.to_sql("WHERE")
+        );
+        assert!(TIMEBAR
+            .exec(&USER, "ch.time=25:00")
+            .expect("This should not panic!")
+            .where_clause()
+            .is_err());
+        assert!(SUCHBAR.exec(&ADMIN, "age.time=10:00").is_err());
+    }
+
+    #[test]
+    fn to_select() {
+        let s = SUCHBAR
+            .exec(&ADMIN, "art=1*")
+            .expect("This should not panic!");
+        assert_eq!(
+            "SELECT artikelnummer, price FROM offer WHERE artikelnummer LIKE '1%'",
+            s.to_select(&["artikelnummer", "price"], "offer")
+        );
+        let s = SUCHBAR
+            .exec(&ADMIN, "; p, ^art")
+            .expect("This should not panic!");
+        assert_eq!(
+            "SELECT * FROM offer ORDER BY price, artikelnummer DESC",
+            s.to_select(&[], "offer")
+        );
+    }
 
-    struct Perm {
-        perms: [&'static str; 2],
+    #[test]
+    fn error_on_empty() {
+        const STRICT: Suchbar = SUCHBAR.with_options(SuchOptions::new().error_on_empty(true));
+        for query in ["", "   ", " \t\n "] {
+            let s = SUCHBAR.exec(&ADMIN, query).expect("This should not panic!");
+            assert_eq!("", s.to_sql("WHERE"));
+            assert!(matches!(
+                STRICT.exec(&ADMIN, query),
+                Err(SuchError::EmptyQuery)
+            ));
+        }
+        assert!(STRICT.exec(&ADMIN, "; ^art").is_ok());
     }
 
-    impl Permeable for Perm {
-        fn has_perm(&self, permission: &str) -> Result<(), PermissionError> {
-            if self.perms.iter().any(|&str| str == permission) {
-                Ok(())
-            } else {
-                Err(PermissionError::denied(permission, "user"))
-            }
+    #[test]
+    fn fields_for() {
+        let fields = SUCHBAR.fields_for(&USER);
+        assert_eq!(
+            vec!["art", "beschreibung", "preis", "changed"],
+            fields
+                .iter()
+                .map(|f| f.aliases[0].as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("art=Duck*", fields[0].example);
+        for info in SUCHBAR.fields_for(&ADMIN) {
+            let s = SUCHBAR
+                .exec(&ADMIN, &info.example)
+                .expect("This should not panic!");
+            assert!(s.where_clause().is_ok(), "{}", info.example);
         }
     }
 
-    const ADMIN: Perm = Perm {
-        perms: ["ACCESS_PRIVATE", "READ_OFFER"],
-    };
+    #[test]
+    fn parse_default_fields() {
+        const SEARCHBOX: Suchbar =
+            SUCHBAR.with_options(SuchOptions::new().default_fields(&["art", "ptext"]));
+        let s = SEARCHBOX
+            .exec(&ADMIN, "Eisen")
+            .expect("This should not panic!");
+        assert_eq!(
+            "( artikelnummer LIKE '%Eisen%' OR positionstext LIKE '%Eisen%' )",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = SEARCHBOX
+            .exec(&ADMIN, "p=12")
+            .expect("This should not panic!");
+        assert_eq!(
+            "price=12",
+            s.where_clause().expect("This should not panic!")
+        );
+        const MISSPELLED: Suchbar =
+            SUCHBAR.with_options(SuchOptions::new().default_fields(&["art", "pttext"]));
+        assert!(matches!(
+            MISSPELLED.exec(&ADMIN, "foo art=x"),
+            Err(SuchError::UnknownField(name)) if name == "pttext"
+        ));
+        assert!(MISSPELLED.exec(&ADMIN, "art=x").is_ok());
+    }
 
-    const USER: Perm = Perm {
-        perms: ["ANYTHING_ELSE", "READ_OFFER"],
-    };
+    #[test]
+    fn collapse_overlaps() {
+        const COLLAPSING: Suchbar =
+            SUCHBAR.with_options(SuchOptions::new().collapse_overlaps(true));
+        for (query, collapsed, kept) in [
+            (
+                "ptext=Eisen ptext=*ise*",
+                "positionstext='Eisen'",
+                "( positionstext='Eisen' AND positionstext LIKE '%ise%' )",
+            ),
+            (
+                "ptext=Eisen OR ptext=Eis*",
+                "positionstext LIKE 'Eis%'",
+                "( positionstext='Eisen' OR positionstext LIKE 'Eis%' )",
+            ),
+            (
+                "ptext=Eisen OR ptext=*Stahl*",
+                "( positionstext='Eisen' OR positionstext LIKE '%Stahl%' )",
+                "( positionstext='Eisen' OR positionstext LIKE '%Stahl%' )",
+            ),
+            (
+                "ptext=Eisen OR art=*ise*",
+                "( positionstext='Eisen' OR artikelnummer LIKE '%ise%' )",
+                "( positionstext='Eisen' OR artikelnummer LIKE '%ise%' )",
+            ),
+        ] {
+            let s = COLLAPSING
+                .exec(&ADMIN, query)
+                .expect("This should not panic!");
+            assert_eq!(collapsed, s.where_clause().expect("This should not panic!"));
+            let s = SUCHBAR.exec(&ADMIN, query).expect("This should not panic!");
+            assert_eq!(kept, s.where_clause().expect("This should not panic!"));
+        }
+    }
 
     #[test]
-    fn give_permission() {
-        assert_eq!(ADMIN.has_perm("READ_OFFER").is_ok(), true);
-        assert_eq!(ADMIN.has_perm("ACCESS_PRIVATE").is_ok(), true);
-        assert_eq!(USER.has_perm("READ_OFFER").is_ok(), true);
-        assert_eq!(USER.has_perm("ACCESS_PRIVATE").is_ok(), false);
+    #[cfg(debug_assertions)]
+    fn unbalanced_sql_fails() {
+        const BROKEN: Suchbar = Suchbar::new(&[
+            DbField::computed(
+                "total",
+                "price * (1 + tax",
+                NUMERIC(12, 2),
+                "READ_OFFER",
+                &["total"],
+            ),
+            DbField::new("name", TEXT, "READ_OFFER", &["name"]),
+        ]);
+        let s = BROKEN
+            .exec(&USER, "total>10")
+            .expect("This should not panic!");
+        assert!(matches!(s.where_clause(), Err(SuchError::ParseError(_))));
+        let s = BROKEN
+            .exec(&USER, "name=\"it's (\" OR name=\"x'y\"")
+            .expect("This should not panic!");
+        assert_eq!(
+            "( name='it''s (' OR name='x''y' )",
+            s.where_clause().expect("This should not panic!")
+        );
     }
 
     #[test]
-    fn parse_not_equal_as_admin() {
+    fn parse_inclusive_upper() {
+        const INCLUSIVE: Suchbar = SUCHBAR.with_options(SuchOptions::new().inclusive_upper(true));
         let s = SUCHBAR
-            .exec(&ADMIN, "age!=123")
+            .exec(&ADMIN, "age=10-19")
             .expect("This should not panic!");
-        assert_eq!("  NOT age=123", s.to_sql(""));
-        let s = SUCHBAR
-            .exec(&ADMIN, "NOT age=123")
+        assert_eq!(
+            "( age>=10 AND age<19 )",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = INCLUSIVE
+            .exec(&ADMIN, "age=10-19")
             .expect("This should not panic!");
-        assert_eq!("  NOT age=123", s.to_sql(""));
-        let s = SUCHBAR
-            .exec(&ADMIN, "ptext!=A")
+        assert_eq!(
+            "( age>=10 AND age<=19 )",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = INCLUSIVE
+            .exec(&ADMIN, "ch='2024-01-01'..'2024-01-31'")
             .expect("This should not panic!");
-        assert_eq!("  NOT positionstext='A'", s.to_sql(""));
+        assert_eq!(
+            "( changed>='2024-01-01' AND changed<'2024-01-31' )",
+            s.where_clause().expect("This should not panic!")
+        );
+    }
 
+    #[test]
+    fn parse_similar() {
         let s = SUCHBAR
-            .exec(&ADMIN, " ptext != AAA*")
+            .exec(&ADMIN, "ptext%%Meyer")
             .expect("This should not panic!");
-        assert_eq!("  NOT positionstext LIKE 'AAA%'", s.to_sql(""));
-
+        assert_eq!(
+            "soundex(positionstext)=soundex('Meyer')",
+            s.where_clause().expect("This should not panic!")
+        );
+        assert_eq!(
+            "beschreibung%%Meyer",
+            s.as_text(Style::Compact).expect("This should not panic!")
+        );
         let s = SUCHBAR
-            .exec(&ADMIN, "NOT ptext == AAA*")
+            .exec(&ADMIN, "ptext !%% \"O'Neil\"")
             .expect("This should not panic!");
-        assert_eq!("  NOT positionstext LIKE 'AAA%'", s.to_sql(""));
-
+        assert_eq!(
+            "soundex(positionstext)<>soundex('O''Neil')",
+            s.where_clause().expect("This should not panic!")
+        );
         let s = SUCHBAR
-            .exec(&ADMIN, "NOT ptext != AAA*")
+            .exec(&ADMIN, "age%%12")
             .expect("This should not panic!");
-        assert_eq!("  positionstext LIKE 'AAA%'", s.to_sql(""));
+        assert!(s.where_clause().is_err());
     }
 
     #[test]
-    fn parse_and_concat() {
-        let s = SUCHBAR
-            .exec(&ADMIN, "age=123 AND ptext=AAA")
+    fn parse_field_groups() {
+        const GROUPS: Suchbar =
+            Suchbar::new(SUCHBAR.db_fields).with_groups(&[("nummern", &["art", "age"])]);
+        let s = GROUPS
+            .exec(&ADMIN, "Nummern=12")
             .expect("This should not panic!");
         assert_eq!(
-            "( age=123 AND positionstext='AAA' )",
+            "( artikelnummer='12' OR age=12 )",
             s.where_clause().unwrap_or_default()
         );
-        let s = SUCHBAR
-            .exec(&ADMIN, "art=123* AND ptext=AAA")
+        let s = GROUPS
+            .exec(&ADMIN, "nummern!=12 art=5")
             .expect("This should not panic!");
         assert_eq!(
-            "( artikelnummer LIKE '123%' AND positionstext='AAA' )",
+            "( NOT ( artikelnummer='12' OR age=12 ) AND artikelnummer='5' )",
             s.where_clause().unwrap_or_default()
         );
+        // the user may search `art`, but not `age`
+        let s = GROUPS
+            .exec(&USER, "nummern=12 OR art=5")
+            .expect("This should not panic!");
+        assert_eq!("artikelnummer='5'", s.where_clause().unwrap_or_default());
+        assert!(GROUPS.validate(&ADMIN, "nummern=12").is_ok());
     }
 
     #[test]
-    fn parse_not_equal_as_user() {
-        let s = SUCHBAR
-            .exec(&USER, "age!=123")
+    fn parse_number_separators() {
+        const GERMAN: Suchbar = SUCHBAR.with_options(SuchOptions::new().thousands_sep('.'));
+        const US: Suchbar =
+            SUCHBAR.with_options(SuchOptions::new().decimal_sep('.').thousands_sep(','));
+        for (suchbar, query) in [
+            (SUCHBAR, "p=1234,56"),
+            (SUCHBAR, "p=1234.56"),
+            (GERMAN, "p=1.234,56"),
+            (US, "p=1,234.56"),
+        ] {
+            let s = suchbar.exec(&ADMIN, query).expect("This should not panic!");
+            assert_eq!(
+                "price=1234.56",
+                s.where_clause().unwrap_or_default(),
+                "{query}"
+            );
+        }
+        let s = US
+            .exec(&ADMIN, "p>1,000 age<1,00")
             .expect("This should not panic!");
-        assert_eq!("", s.to_sql(""));
-        let s = SUCHBAR
-            .exec(&USER, "NOT age=123")
+        assert_eq!(
+            "( price>1000 AND age<100 )",
+            s.where_clause().unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn parse_whitespace_in_values() {
+        for (query, sql) in [
+            ("ptext= spaced ", "positionstext='spaced'"),
+            ("ptext=\" spaced \"", "positionstext=' spaced '"),
+            ("ptext=' two  spaces '", "positionstext=' two  spaces '"),
+            ("ptext=a\\ b", "positionstext='a b'"),
+            (
+                "ptext= a - b ",
+                "( positionstext>='a' AND positionstext<'b' )",
+            ),
+            (
+                "ptext=\" a \"-\"b \"",
+                "( positionstext>=' a ' AND positionstext<'b ' )",
+            ),
+        ] {
+            let s = SUCHBAR.exec(&ADMIN, query).expect("This should not panic!");
+            assert_eq!(sql, s.where_clause().unwrap_or_default(), "{query}");
+        }
+    }
+
+    #[test]
+    fn parse_quoted_values() {
+        for (query, sql) in [
+            ("ptext=\"quoted value\"", "positionstext='quoted value'"),
+            ("ptext='single quoted'", "positionstext='single quoted'"),
+            (
+                "ptext!='single quoted'",
+                "NOT positionstext='single quoted'",
+            ),
+            ("ptext=\"quoted*\"", "positionstext LIKE 'quoted%'"),
+            (
+                "\"quoted value\"",
+                "( artikelnummer LIKE '%quoted value%' OR positionstext LIKE '%quoted value%' )",
+            ),
+        ] {
+            let s = SUCHBAR.exec(&ADMIN, query).expect("This should not panic!");
+            assert_eq!(sql, s.where_clause().unwrap_or_default(), "{query}");
+        }
+    }
+
+    #[test]
+    fn parse_uuid() {
+        const UUIDBAR: Suchbar = Suchbar::new(&[
+            DbField::new("id", UUID, "READ_OFFER", &["id"]),
+            DbField::new("name", TEXT, "READ_OFFER", &["name"]),
+        ]);
+        let s = UUIDBAR
+            .exec(&USER, "id=123e4567-e89b-12d3-a456-426614174000")
             .expect("This should not panic!");
-        assert_eq!("", s.to_sql(""));
-        let s = SUCHBAR
-            .exec(&USER, "ptext!=A")
+        assert_eq!(
+            "id='123e4567-e89b-12d3-a456-426614174000'",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = UUIDBAR
+            .exec(&USER, "id=67e55044*")
             .expect("This should not panic!");
-        assert_eq!("  NOT positionstext='A'", s.to_sql(""));
-
-        let s = SUCHBAR
-            .exec(&USER, " ptext != AAA*")
+        assert_eq!(
+            "id::TEXT LIKE '67e55044%'",
+            s.where_clause().expect("This should not panic!")
+        );
+        let s = UUIDBAR
+            .exec(&USER, "id=12345-67")
             .expect("This should not panic!");
-        assert_eq!("  NOT positionstext LIKE 'AAA%'", s.to_sql(""));
-
-        let s = SUCHBAR
-            .exec(&USER, "NOT ptext == AAA*")
+        assert!(s.where_clause().is_err());
+        assert!(UUIDBAR.validate(&USER, "id=xyz").is_err());
+        let s = UUIDBAR
+            .exec(&USER, "id=123e4567-e89b-12d3-a456-42661417400g")
             .expect("This should not panic!");
-        assert_eq!("  NOT positionstext LIKE 'AAA%'", s.to_sql(""));
+        assert!(s.where_clause().is_err());
+    }
 
+    #[test]
+    fn parse_escaped_quotes() {
+        for (query, sql) in [
+            (r#"desc="10\" monitor""#, r#"positionstext='10" monitor'"#),
+            (r#"desc="10"" monitor""#, r#"positionstext='10" monitor'"#),
+            (r#"desc='Peter\'s'"#, "positionstext='Peter''s'"),
+            (r#"desc='Peter''s'"#, "positionstext='Peter''s'"),
+            (r#"desc="Peter's""#, "positionstext='Peter''s'"),
+            (r"desc='C:\'", r"positionstext='C:\'"),
+            (
+                r"desc='C:\' art=5",
+                r"( positionstext='C:\' AND artikelnummer='5' )",
+            ),
+            (
+                r#"desc='' art=5"#,
+                "( positionstext='' AND artikelnummer='5' )",
+            ),
+        ] {
+            let s = SUCHBAR.exec(&ADMIN, query).expect("This should not panic!");
+            assert_eq!(sql, s.where_clause().unwrap_or_default(), "{query}");
+        }
         let s = SUCHBAR
-            .exec(&USER, "NOT ptext != AAA*")
+            .exec(&ADMIN, r#"desc="10\" Peter's""#)
             .expect("This should not panic!");
-        assert_eq!("  positionstext LIKE 'AAA%'", s.to_sql(""));
+        let text = s.as_text(Style::Compact).expect("This should not panic!");
+        assert_eq!(r#"beschreibung='10" Peter''s'"#, text);
+        let again = SUCHBAR.exec(&ADMIN, text).expect("This should not panic!");
+        assert_eq!(s.where_clause().ok(), again.where_clause().ok());
     }
 
     #[test]
-    fn parse_integer_query_std() {
-        let s = SUCHBAR.exec(&ADMIN, "123").expect("This should not panic!");
+    fn table_prefix() {
+        const PREFIXED: Suchbar = SUCHBAR.with_options(SuchOptions::new().table_prefix("offers"));
+        let s = PREFIXED
+            .exec(&ADMIN, "art=5* OR p>10; ^art, p")
+            .expect("This should not panic!");
         assert_eq!(
-            "  ( artikelnummer LIKE '%123%' OR positionstext LIKE '%123%' OR \
-            price=123 OR age=123 OR promille=123 )",
-            s.to_sql("")
+            " WHERE ( offers.artikelnummer LIKE '5%' OR offers.price>10 ) \
+             ORDER BY offers.artikelnummer DESC, offers.price",
+            s.to_sql("WHERE")
+        );
+        assert_eq!(
+            "( o.artikelnummer LIKE '5%' OR o.price>10 )",
+            s.to_sql_aliased("o").expect("This should not panic!")
         );
         let s = SUCHBAR
-            .exec(&ADMIN, "1234")
+            .exec(&ADMIN, "art=5*; art")
             .expect("This should not panic!");
         assert_eq!(
-            "  ( artikelnummer LIKE '%1234%' OR positionstext LIKE '%1234%' \
-            OR price=1234 )",
-            s.to_sql("")
+            " WHERE artikelnummer LIKE '5%' ORDER BY artikelnummer",
+            s.to_sql("WHERE")
         );
     }
 
     #[test]
-    fn parse_integer_query_like() {
-        let likebar = Suchbar {
-            options: SuchOptions {
-                like_in_numerics: true,
-            },
-            db_fields: SUCHBAR.db_fields,
-        };
-        let s = likebar.exec(&ADMIN, "123").expect("This should not panic!");
+    fn tables_of_fields() {
+        const JOINED: Suchbar = Suchbar::new(&[
+            DbField::new("name", TEXT, "READ_OFFER", &["name"]),
+            DbField::new("city", TEXT, "READ_OFFER", &["city"]).in_table("addresses"),
+        ]);
+        let s = JOINED
+            .exec(&ADMIN, "name=Duck city=Berlin; city")
+            .expect("This should not panic!");
         assert_eq!(
-            "  ( artikelnummer LIKE '%123%' OR positionstext LIKE '%123%' OR price::TEXT LIKE '%123%' \
-            OR age::TEXT LIKE '%123%' OR promille::TEXT LIKE '%123%' )",
-            s.to_sql("")
+            " WHERE ( name='Duck' AND addresses.city='Berlin' ) ORDER BY addresses.city",
+            s.to_sql("WHERE")
         );
-        let s = likebar
-            .exec(&ADMIN, "1234")
+        assert_eq!(
+            "( p.name='Duck' AND addresses.city='Berlin' )",
+            s.to_sql_aliased("p").expect("This should not panic!")
+        );
+        let s = JOINED
+            .with_options(SuchOptions::new().table_prefix("persons"))
+            .exec(&ADMIN, "name=Duck city=Berlin")
             .expect("This should not panic!");
         assert_eq!(
-            "  ( artikelnummer LIKE '%1234%' OR positionstext LIKE '%1234%' OR \
-            price::TEXT LIKE '%1234%' )",
-            s.to_sql("")
+            "( persons.name='Duck' AND addresses.city='Berlin' )",
+            s.where_clause().unwrap_or_default()
         );
     }
 
     #[test]
-    fn parse_like_query() {
-        let s = SUCHBAR
-            .exec(&ADMIN, "art='2332*'")
-            .expect("This should not panic!");
-        assert_eq!("  artikelnummer LIKE '2332%'", s.to_sql(""));
+    fn empty_where_clause() {
+        for (query, empty, denials) in [
+            ("", true, false),
+            ("; ^art", true, false),
+            ("art=5", false, false),
+            ("age>18", true, true),
+            ("age>18 OR art=5", false, true),
+        ] {
+            let s = SUCHBAR.exec(&USER, query).expect("This should not panic!");
+            assert_eq!(empty, s.is_empty(), "{query}");
+            assert_eq!(denials, s.has_denials(), "{query}");
+        }
+    }
+
+    #[test]
+    fn all_denied() {
         let s = SUCHBAR
-            .exec(&ADMIN, "art=2332*")
+            .exec(&USER, "age=18")
             .expect("This should not panic!");
-        assert_eq!("  artikelnummer LIKE '2332%'", s.to_sql(""));
+        assert!(s.all_denied());
+        assert_eq!(" WHERE FALSE", s.to_sql("WHERE"));
         let s = SUCHBAR
-            .exec(&ADMIN, "art=2332$")
+            .exec(&USER, "age=18 OR promille<5")
             .expect("This should not panic!");
-        assert_eq!("  artikelnummer LIKE '%2332'", s.to_sql(""));
+        assert!(s.all_denied());
+        assert_eq!("FALSE", s.where_clause().unwrap_or_default());
+        for query in ["", "; ^art", "age=18 OR art=5"] {
+            let s = SUCHBAR.exec(&USER, query).expect("This should not panic!");
+            assert!(!s.all_denied(), "{query}");
+        }
         let s = SUCHBAR
-            .exec(&ADMIN, "art='*2332*'")
+            .exec(&ADMIN, "age=18")
             .expect("This should not panic!");
-        assert_eq!("  artikelnummer LIKE '%2332%'", s.to_sql(""));
+        assert!(!s.all_denied());
         let s = SUCHBAR
-            .exec(&ADMIN, "art=^'2332'")
+            .with_options(SuchOptions::new().empty_on_all_denied(EmptyPolicy::MatchAll))
+            .exec(&USER, "age=18")
             .expect("This should not panic!");
-        assert_eq!("  artikelnummer LIKE '2332%'", s.to_sql(""));
-    }
-
-    #[test]
-    fn parse_misc_query() {
-        let query = r#"ano!=23342 AND (desc=^"irgend ein langer Text!" OR price='35,12'); artnr, ^nummer, age"#;
-        let s = SUCHBAR.exec(&ADMIN, query).expect("This should not panic!");
-        assert_eq!(
-            "  ( NOT artikelnummer='23342' AND ( positionstext LIKE 'irgend ein langer Text!%' \
-            OR price=35.12 ) ) ORDER BY artikelnummer, promille DESC, age",
-            s.to_sql("")
-        );
+        assert!(s.all_denied());
+        assert_eq!("", s.to_sql("WHERE"));
     }
 
     #[test]
-    fn parse_from_to_values() {
+    fn compare_where_clauses() {
         let s = SUCHBAR
-            .exec(&ADMIN, "age=10-19")
+            .exec(&ADMIN, "art=5* p>10; ^age")
             .expect("This should not panic!");
-        assert_eq!("  ( age>=10 AND age<19 )", s.to_sql(""));
+        let same = SUCHBAR
+            .exec(&ADMIN, "artikelnummer=5* AND preis>10; ^alter")
+            .expect("This should not panic!");
+        assert_eq!(s, same);
+        assert_eq!(s, s.clone());
+        assert_eq!(s.sql_term, s.sql_term.clone());
+        let other = SUCHBAR
+            .exec(&ADMIN, "art=5* p>10; age")
+            .expect("This should not panic!");
+        assert_ne!(s, other);
+        assert_eq!(s.sql_term, other.sql_term);
+        let other = SUCHBAR
+            .exec(&ADMIN, "art=5* p>11; ^age")
+            .expect("This should not panic!");
+        assert_ne!(s.sql_term, other.sql_term);
     }
 
     #[test]
-    fn parse_like_somewhere() {
+    fn parse_strict_fields() {
         let s = SUCHBAR
-            .exec(&ADMIN, "*Superman*")
+            .exec(&ADMIN, "xyz=5")
             .expect("This should not panic!");
-        assert_eq!(
-            " WHERE ( artikelnummer LIKE '%Superman%' OR positionstext LIKE '%Superman%' )",
-            s.to_sql("WHERE")
-        );
+        assert!(s.where_clause().is_ok());
 
-        let s = SUCHBAR
-            .exec(&ADMIN, "Superman Batman")
+        const STRICT: Suchbar = SUCHBAR.with_options(SuchOptions::new().strict_fields(true));
+        assert!(matches!(
+            STRICT.exec(&ADMIN, "xyz=5"),
+            Err(SuchError::UnknownField(name)) if name == "xyz"
+        ));
+        assert!(matches!(
+            STRICT.exec(&ADMIN, "art=5 OR xyz~5"),
+            Err(SuchError::UnknownField(name)) if name == "xyz"
+        ));
+        let s = STRICT
+            .exec(&ADMIN, "art=5 Eisen")
             .expect("This should not panic!");
         assert_eq!(
-            " WHERE ( ( artikelnummer LIKE '%Superman%' OR positionstext LIKE '%Superman%' ) AND \
-            ( artikelnummer LIKE '%Batman%' OR positionstext LIKE '%Batman%' ) )",
-            s.to_sql("WHERE")
+            "( artikelnummer='5' AND ( artikelnummer LIKE '%Eisen%' OR positionstext LIKE '%Eisen%' ) )",
+            s.where_clause().unwrap_or_default()
         );
-        // age = *5*
-        let s = SUCHBAR
-            .exec(&ADMIN, "artnr = *5*")
-            .expect("This should not panic!");
-        assert_eq!(" WHERE artikelnummer LIKE '%5%'", s.to_sql("WHERE"));
     }
 
     #[test]
-    fn parse_iso_dates() {
-        let s = SUCHBAR
-            .exec(&ADMIN, "ch=2022-12-24")
-            .expect("This should not panic!");
-        assert_eq!(" WHERE changed='2022-12-24'", s.to_sql("WHERE"));
-
+    fn parse_same_field_or() {
+        const SAME_FIELD_OR: Suchbar = SUCHBAR.with_options(SuchOptions::new().same_field_or(true));
+        for (query, expected) in [
+            (
+                "art=100 art=200",
+                "( artikelnummer='100' OR artikelnummer='200' )",
+            ),
+            (
+                "art=100 p>10 ano=200 artnr=300",
+                "( ( artikelnummer='100' OR artikelnummer='200' OR artikelnummer='300' ) AND price>10 )",
+            ),
+            (
+                "art=100 art=2*",
+                "( artikelnummer='100' AND artikelnummer LIKE '2%' )",
+            ),
+        ] {
+            let s = SAME_FIELD_OR
+                .exec(&ADMIN, query)
+                .expect("This should not panic!");
+            assert_eq!(expected, s.where_clause().expect("This should not panic!"));
+        }
         let s = SUCHBAR
-            .exec(&ADMIN, r#"ch="2022-12-24""#)
+            .exec(&ADMIN, "art=100 art=200")
             .expect("This should not panic!");
-        assert_eq!(" WHERE changed='2022-12-24'", s.to_sql("WHERE"));
+        assert_eq!(
+            "( artikelnummer='100' AND artikelnummer='200' )",
+            s.where_clause().expect("This should not panic!")
+        );
     }
 
     #[test]
-    fn parse_natural_language_dates() {
-        let s = SUCHBAR
-            .exec(&ADMIN, "ch=Jan")
-            .expect("This should not panic!");
-        assert_eq!(" WHERE changed='2023-01-01'", s.to_sql("WHERE"));
+    fn merge_clauses() {
+        let exec = |query: &str| SUCHBAR.exec(&ADMIN, query).expect("This should not panic!");
+        assert_eq!(
+            " WHERE ( ( artikelnummer LIKE '1%' OR price>10 ) AND age>18 ) ORDER BY price DESC, age",
+            exec("art=1* OR p>10; ^p").and(exec("age>18; p, age")).to_sql("WHERE")
+        );
+        assert_eq!(
+            " WHERE ( artikelnummer LIKE '1%' OR ( age>18 AND price<5 ) )",
+            exec("art=1*")
+                .or(exec("age>18 p<5"))
+                .expect("This should not panic!")
+                .to_sql("WHERE")
+        );
+        assert_eq!(
+            " WHERE age>18 ORDER BY artikelnummer",
+            exec("; art").and(exec("age>18")).to_sql("WHERE")
+        );
+        assert_eq!(
+            " WHERE age>18",
+            exec("age>18").and(exec("")).to_sql("WHERE")
+        );
+    }
 
-        let s = SUCHBAR
-            .exec(&ADMIN, r#"ch=24.12.2022"#)
-            .expect("This should not panic!");
-        assert_eq!(" WHERE changed='2022-12-24'", s.to_sql("WHERE"));
-        let s = SUCHBAR
-            .exec(&ADMIN, r#"ch='Feb'-'Dez'"#)
+    #[test]
+    fn with_raw() {
+        let mut s = SUCHBAR.exec(&ADMIN, "").expect("This should not panic!");
+        s.with_raw("deleted_at IS NULL");
+        assert_eq!(" WHERE deleted_at IS NULL", s.to_sql("WHERE"));
+        let mut s = SUCHBAR
+            .exec(&ADMIN, "art=1* OR p>10")
             .expect("This should not panic!");
+        s.with_raw("deleted_at IS NULL");
+        s.with_raw("tenant_id = 7");
         assert_eq!(
-            " WHERE ( changed>='2023-02-01' AND changed<'2024-01-01' )",
-            s.to_sql("WHERE")
+            "( ( artikelnummer LIKE '1%' OR price>10 ) AND deleted_at IS NULL AND tenant_id = 7 )",
+            s.where_clause().expect("This should not panic!")
         );
-        let s = SUCHBAR
-            .exec(&ADMIN, r#"ch=Feb-Dez"#)
-            .expect("This should not panic!");
         assert_eq!(
-            " WHERE ( changed>='2023-02-01' AND changed<'2024-01-01' )",
-            s.to_sql("WHERE")
+            "(art=1*||preis>10)",
+            s.as_text(Style::Compact).expect("This should not panic!")
         );
     }
 
     #[test]
-    fn list_sort_by_fields() {
-        let s = SUCHBAR
-            .exec(&ADMIN, ";age, art")
-            .expect("This should not panic!");
-        assert_eq!(" ORDER BY age, artikelnummer", s.to_sql("WHERE"));
-        let s = SUCHBAR
-            .exec(&ADMIN, ";art, ^p, ch")
+    fn with_raw_when_denied() {
+        let mut s = SUCHBAR
+            .with_options(SuchOptions::new().empty_on_all_denied(EmptyPolicy::MatchAll))
+            .exec(&USER, "age=5")
             .expect("This should not panic!");
+        s.with_raw("tenant_id = 7");
         assert_eq!(
-            " ORDER BY artikelnummer, price DESC, changed",
-            s.to_sql("WHERE")
+            "tenant_id = 7",
+            s.where_clause().expect("This should not panic!")
+        );
+        assert_eq!(
+            "SELECT COUNT(*) FROM t WHERE tenant_id = 7",
+            s.to_count_sql("t")
         );
+        let mut s = SUCHBAR
+            .exec(&USER, "age=5")
+            .expect("This should not panic!");
+        s.with_raw("tenant_id = 7");
+        assert_eq!(" WHERE ( FALSE AND tenant_id = 7 )", s.to_sql("WHERE"));
     }
 }
@@ -0,0 +1,108 @@
+use crate::comp_op::CompOp;
+use crate::db_field::DbField;
+use crate::sql_term::SQLTerm;
+use timewarp::Direction;
+
+/// Walks an `SQLTerm` to turn it into another representation, e.g. a filter of another database.
+/// `SQLTerm::accept` calls the method of the term's variant, the visitor descends into groups
+/// itself by calling `accept` on their terms. `SQLTerm::as_text` is implemented this way.
+///
+/// # Example
+/// ```rust
+/// use permeable::AllowAllPermission;
+/// use suchbar::*;
+/// use suchbar::DbType::{INTEGER, TEXT};
+/// use timewarp::Direction;
+///
+/// /// Emits a MongoDB filter.
+/// struct Mongo;
+///
+/// impl Mongo {
+///     fn group(&mut self, op: &str, terms: &[SQLTerm]) -> String {
+///         if let [term] = terms {
+///             return term.accept(self);
+///         }
+///         let v = terms.iter().map(|t| t.accept(self)).collect::<Vec<_>>();
+///         format!("{{\"{op}\": [{}]}}", v.join(", "))
+///     }
+/// }
+///
+/// impl SqlTermVisitor for Mongo {
+///     type Output = String;
+///
+///     fn visit_and(&mut self, terms: &[SQLTerm]) -> String {
+///         self.group("$and", terms)
+///     }
+///     fn visit_or(&mut self, terms: &[SQLTerm]) -> String {
+///         self.group("$or", terms)
+///     }
+///     fn visit_not(&mut self, term: &SQLTerm) -> String {
+///         format!("{{\"$nor\": [{}]}}", term.accept(self))
+///     }
+///     fn visit_value(&mut self, f: &DbField, op: CompOp, _: Direction, v: &str) -> String {
+///         let op = match op {
+///             CompOp::Gt => "$gt",
+///             CompOp::Lt => "$lt",
+///             CompOp::NotEqual => "$ne",
+///             _ => "$eq",
+///         };
+///         format!("{{\"{}\": {{\"{op}\": \"{v}\"}}}}", f.db_name)
+///     }
+///     fn visit_like(&mut self, f: &DbField, v: &str) -> String {
+///         format!("{{\"{}\": {{\"$regex\": \"^{}$\"}}}}", f.db_name, v.replace('*', ".*"))
+///     }
+///     fn visit_denied(&mut self, _: &DbField) -> String {
+///         String::from("{\"$expr\": false}")
+///     }
+///     fn visit_raw(&mut self, _: &str) -> String {
+///         String::from("{}")
+///     }
+/// }
+///
+/// const SUCHBAR: Suchbar = Suchbar::new(&[
+///   DbField::new("name", TEXT, "STD", &["name", "n"]),
+///   DbField::new("age", INTEGER(0, 150), "STD", &["age", "a"]),
+/// ]);
+///
+/// let exec = SUCHBAR.exec(&AllowAllPermission(), "n=Duck* a>18").unwrap();
+/// assert_eq!(
+///   r#"{"$and": [{"name": {"$regex": "^Duck.*$"}}, {"age": {"$gt": "18"}}]}"#,
+///   exec.accept(&mut Mongo)
+/// );
+/// ```
+pub trait SqlTermVisitor {
+    type Output;
+
+    fn visit_and(&mut self, terms: &[SQLTerm]) -> Self::Output;
+    fn visit_or(&mut self, terms: &[SQLTerm]) -> Self::Output;
+    fn visit_not(&mut self, term: &SQLTerm) -> Self::Output;
+    fn visit_value(
+        &mut self,
+        field: &DbField,
+        comp_op: CompOp,
+        direction: Direction,
+        value: &str,
+    ) -> Self::Output;
+    /// A similarity search, `value` has wildcards in glob-style.
+    fn visit_like(&mut self, field: &DbField, value: &str) -> Self::Output;
+    /// The user isn't allowed to search the `field`.
+    fn visit_denied(&mut self, field: &DbField) -> Self::Output;
+    /// SQL given by the application, see `WhereClause::with_raw`.
+    fn visit_raw(&mut self, sql: &str) -> Self::Output;
+}
+
+impl SQLTerm {
+    /// Calls the method of `visitor` matching this term, see `SqlTermVisitor`.
+    pub fn accept<V: SqlTermVisitor + ?Sized>(&self, visitor: &mut V) -> V::Output {
+        use SQLTerm::{AND, DENIED, LIKE, NOT, OR, RAW, VALUE};
+        match self {
+            AND(vec) => visitor.visit_and(vec),
+            OR(vec) => visitor.visit_or(vec),
+            NOT(term) => visitor.visit_not(term),
+            VALUE(f, eq, d, v) => visitor.visit_value(f, *eq, *d, v),
+            LIKE(f, v) => visitor.visit_like(f, v),
+            DENIED(f) => visitor.visit_denied(f),
+            RAW(sql) => visitor.visit_raw(sql),
+        }
+    }
+}